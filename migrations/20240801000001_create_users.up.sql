@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS users
+(
+    id                BIGSERIAL PRIMARY KEY,
+    username          VARCHAR(100)            NOT NULL,
+    email             VARCHAR(50)             NOT NULL,
+    password          VARCHAR(500)            NOT NULL,
+    bio               VARCHAR(500)            NULL,
+    image             VARCHAR(50)             NULL,
+    registration_date TIMESTAMP DEFAULT now() NOT NULL,
+    modified_date     TIMESTAMP DEFAULT now() NOT NULL,
+    deleted           BOOLEAN   DEFAULT false NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS users_email_index
+    ON users (email);
+
+CREATE INDEX IF NOT EXISTS users_username_index
+    ON users (username);
@@ -0,0 +1,38 @@
+CREATE TABLE IF NOT EXISTS user_follow
+(
+    id           BIGSERIAL PRIMARY KEY,
+    follower_id  BIGINT                  NOT NULL,
+    following_id BIGINT                  NOT NULL,
+    created_at   TIMESTAMP DEFAULT now() NOT NULL,
+    updated_at   TIMESTAMP               NULL,
+    deleted      BOOLEAN   DEFAULT false NOT NULL,
+    CONSTRAINT user_follow_users_id_fk
+        FOREIGN KEY (follower_id) REFERENCES users (id),
+    CONSTRAINT user_follow_users_id_fk_2
+        FOREIGN KEY (following_id) REFERENCES users (id)
+);
+
+-- Databases created from the old migration/user_follow.sql script have a TIME
+-- updated_at, a nullable deleted and no created_at default.
+DO
+$$
+BEGIN
+    IF EXISTS (SELECT 1
+               FROM information_schema.columns
+               WHERE table_name = 'user_follow'
+                 AND column_name = 'updated_at'
+                 AND data_type = 'time without time zone') THEN
+        ALTER TABLE user_follow ALTER COLUMN updated_at TYPE TIMESTAMP USING NULL;
+    END IF;
+END
+$$;
+
+UPDATE user_follow SET deleted = false WHERE deleted IS NULL;
+
+ALTER TABLE user_follow
+    ALTER COLUMN created_at SET DEFAULT now(),
+    ALTER COLUMN deleted SET DEFAULT false,
+    ALTER COLUMN deleted SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS user_follow_follower_following_index
+    ON user_follow (follower_id, following_id);
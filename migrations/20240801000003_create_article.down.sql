@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS article_favorite;
+DROP TABLE IF EXISTS article_tag;
+DROP TABLE IF EXISTS article;
+DROP TABLE IF EXISTS tag;
@@ -0,0 +1,45 @@
+CREATE TABLE IF NOT EXISTS tag
+(
+    tag_name VARCHAR(50) NOT NULL,
+    CONSTRAINT tag_pk
+        PRIMARY KEY (tag_name)
+);
+
+CREATE TABLE IF NOT EXISTS article
+(
+    id          BIGSERIAL PRIMARY KEY,
+    user_id     BIGINT                  NOT NULL,
+    title       VARCHAR(100)            NOT NULL,
+    slug        VARCHAR(150)            NOT NULL,
+    description VARCHAR(500)            NOT NULL,
+    body        VARCHAR(500)            NOT NULL,
+    created_at  TIMESTAMP DEFAULT now() NOT NULL,
+    updated_at  TIMESTAMP DEFAULT now() NOT NULL,
+    deleted     BOOLEAN   DEFAULT false NOT NULL,
+    CONSTRAINT article_pk
+        UNIQUE (slug),
+    CONSTRAINT article_users_id_fk
+        FOREIGN KEY (user_id) REFERENCES users (id)
+);
+
+CREATE TABLE IF NOT EXISTS article_tag
+(
+    id         BIGSERIAL PRIMARY KEY,
+    article_id BIGINT      NOT NULL,
+    tag_name   VARCHAR(50) NOT NULL,
+    CONSTRAINT article_tag_article_id_fk
+        FOREIGN KEY (article_id) REFERENCES article (id),
+    CONSTRAINT article_tag_tag_tag_name_fk
+        FOREIGN KEY (tag_name) REFERENCES tag (tag_name)
+);
+
+CREATE TABLE IF NOT EXISTS article_favorite
+(
+    id               BIGSERIAL PRIMARY KEY,
+    article_id       BIGINT NOT NULL,
+    favorite_user_id BIGINT NOT NULL,
+    CONSTRAINT article_favorite_article_id_fk
+        FOREIGN KEY (article_id) REFERENCES article (id),
+    CONSTRAINT article_favorite_users_id_fk
+        FOREIGN KEY (favorite_user_id) REFERENCES users (id)
+);
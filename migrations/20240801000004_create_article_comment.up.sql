@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS article_comment
+(
+    id         BIGSERIAL PRIMARY KEY,
+    article_id BIGINT                  NOT NULL,
+    user_id    BIGINT                  NOT NULL,
+    body       VARCHAR(1000)           NOT NULL,
+    created_at TIMESTAMP DEFAULT now() NOT NULL,
+    updated_at TIMESTAMP DEFAULT now() NOT NULL,
+    deleted    BOOLEAN   DEFAULT false NOT NULL,
+    CONSTRAINT article_comment_article_id_fk
+        FOREIGN KEY (article_id) REFERENCES article (id),
+    CONSTRAINT article_comment_users_id_fk
+        FOREIGN KEY (user_id) REFERENCES users (id)
+);
+
+CREATE INDEX IF NOT EXISTS article_comment_article_id_index
+    ON article_comment (article_id);
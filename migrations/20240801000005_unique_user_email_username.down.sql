@@ -0,0 +1,8 @@
+CREATE INDEX IF NOT EXISTS users_email_index
+    ON users (email);
+
+CREATE INDEX IF NOT EXISTS users_username_index
+    ON users (username);
+
+DROP INDEX IF EXISTS users_email_unique_index;
+DROP INDEX IF EXISTS users_username_unique_index;
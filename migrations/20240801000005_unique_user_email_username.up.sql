@@ -0,0 +1,10 @@
+-- Uniqueness was only checked by UserService::is_exist, which races with
+-- concurrent sign ups. Like is_exist, it covers soft deleted users too.
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_unique_index
+    ON users (email);
+
+CREATE UNIQUE INDEX IF NOT EXISTS users_username_unique_index
+    ON users (username);
+
+DROP INDEX IF EXISTS users_email_index;
+DROP INDEX IF EXISTS users_username_index;
@@ -0,0 +1,4 @@
+-- Bumped by every update, so a client can send back the version it read in
+-- If-Match and have a concurrent edit rejected instead of overwritten.
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS version BIGINT DEFAULT 1 NOT NULL;
@@ -1,5 +1,11 @@
-use chrono::{DateTime, Utc};
+use std::sync::{Arc, RwLock};
 
+use chrono::{DateTime, Duration, Utc};
+
+pub type DynClock = Arc<dyn Clock + Send + Sync>;
+
+/// Source of the current time. Everything time dependent (token issue and expiry,
+/// stored timestamps) reads it from here so tests can control it.
 pub trait Clock {
     fn now(&self) -> DateTime<Utc>;
 }
@@ -11,3 +17,50 @@ impl Clock for RealClock {
         chrono::Utc::now()
     }
 }
+
+/// Clock that stays at a fixed instant until a test moves it.
+pub struct MockClock {
+    now: RwLock<DateTime<Utc>>,
+}
+
+impl MockClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: RwLock::new(now),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.write().unwrap() = now;
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.write().unwrap();
+        *now += duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.read().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::{Clock, MockClock};
+
+    #[test]
+    fn mock_clock_moves_only_when_told_test() {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let clock = MockClock::new(start);
+
+        assert_eq!(clock.now(), start);
+        clock.advance(Duration::minutes(5));
+        assert_eq!(clock.now(), start + Duration::minutes(5));
+        clock.set(start);
+        assert_eq!(clock.now(), start);
+    }
+}
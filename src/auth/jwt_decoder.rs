@@ -1,20 +1,30 @@
+use std::sync::Arc;
+
 use anyhow::anyhow;
 use jsonwebtoken::{decode, DecodingKey, Validation};
-use log::error;
+use tracing::error;
 
+use crate::auth::clock::DynClock;
 use crate::auth::jwt_payload::JwtPayload;
 
+pub type ArcJwtDecoder = Arc<JwtDecoder>;
+
 pub struct JwtDecoder {
     secret_key: String,
+    clock: DynClock,
 }
 
 impl JwtDecoder {
-    pub fn new(secret_key: String) -> JwtDecoder {
-        JwtDecoder { secret_key }
+    pub fn new(secret_key: String, clock: DynClock) -> JwtDecoder {
+        JwtDecoder { secret_key, clock }
     }
+
+    /// Verifies the signature, then checks `exp` against the injected clock instead
+    /// of the system time.
     pub fn decode_token(&self, token: &String) -> anyhow::Result<JwtPayload> {
         let mut validation = Validation::default();
         validation.leeway = 0;
+        validation.validate_exp = false;
 
         let token_data = decode::<JwtPayload>(
             token,
@@ -23,6 +33,10 @@ impl JwtDecoder {
         );
 
         match token_data {
+            Ok(result) if result.claims.is_expired(self.clock.as_ref()) => {
+                error!("jwt verify error : token expired");
+                Err(anyhow!("jwt verify failed ExpiredSignature"))
+            }
             Ok(result) => Ok(result.claims),
             Err(err) => {
                 error!("jwt verify error : {err}");
@@ -34,14 +48,25 @@ impl JwtDecoder {
 
 #[cfg(test)]
 mod tests {
-    use crate::{auth::jwt_encoder::JwtEncoder, user::domain::user::User};
+    use std::sync::Arc;
+
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::{
+        auth::{clock::MockClock, jwt_encoder::JwtEncoder},
+        user::domain::user::User,
+    };
 
     use super::JwtDecoder;
 
     const KEY: &str = "secret_key";
 
-    fn fixture_token() -> String {
-        let encoder = JwtEncoder::from(String::from(KEY));
+    #[test]
+    fn jwt_decode_test() {
+        let clock = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        ));
+        let encoder = JwtEncoder::new(String::from(KEY), clock.clone());
         let user = User::new(
             1,
             String::from("email"),
@@ -50,14 +75,8 @@ mod tests {
             None,
             None,
         );
-        let token = encoder.create_token(&user);
-        return token.unwrap();
-    }
-
-    #[test]
-    fn jwt_decode_test() {
-        let token = fixture_token();
-        let decoder = JwtDecoder::new(String::from(KEY));
+        let token = encoder.create_token(&user).unwrap();
+        let decoder = JwtDecoder::new(String::from(KEY), clock);
 
         let jwt_payload = decoder.decode_token(&token);
         assert_eq!(jwt_payload.is_ok(), true);
@@ -65,4 +84,28 @@ mod tests {
         let jwt_payload = jwt_payload.unwrap();
         assert_eq!(jwt_payload.id(), 1);
     }
+
+    #[test]
+    fn jwt_expires_with_clock_test() {
+        let clock = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        ));
+        let encoder = JwtEncoder::new(String::from(KEY), clock.clone());
+        let user = User::new(
+            1,
+            String::from("email"),
+            String::from("passwrod"),
+            String::from("username"),
+            None,
+            None,
+        );
+        let token = encoder.create_token(&user).unwrap();
+        let decoder = JwtDecoder::new(String::from(KEY), clock.clone());
+
+        clock.advance(Duration::hours(3));
+        assert!(decoder.decode_token(&token).is_ok());
+
+        clock.advance(Duration::seconds(1));
+        assert!(decoder.decode_token(&token).is_err());
+    }
 }
@@ -1,39 +1,44 @@
 use std::sync::Arc;
 
 use anyhow::Context;
+use chrono::Duration;
 use jsonwebtoken::{encode, EncodingKey, Header};
 
-use crate::auth::clock::RealClock;
+use crate::auth::clock::{DynClock, RealClock};
 use crate::config::RealWorldResult;
 use crate::user::domain::user::User;
 
-use super::clock::Clock;
 use super::jwt_payload::JwtPayload;
 
-unsafe impl Send for JwtEncoder {}
-unsafe impl Sync for JwtEncoder {}
-
 pub type ArcJwtEncoder = Arc<JwtEncoder>;
 
+const DEFAULT_TOKEN_TTL_HOURS: i64 = 3;
+
 pub struct JwtEncoder {
     secret_key: String,
-    clock: Box<dyn Clock>,
+    clock: DynClock,
+    token_ttl: Duration,
 }
 
 impl JwtEncoder {
-    pub fn new(secret_key: String, clock: Box<dyn Clock>) -> JwtEncoder {
-        JwtEncoder { secret_key, clock }
-    }
-
-    pub fn from(secret_key: String) -> JwtEncoder {
+    pub fn new(secret_key: String, clock: DynClock) -> JwtEncoder {
         JwtEncoder {
             secret_key,
-            clock: Box::new(RealClock),
+            clock,
+            token_ttl: Duration::hours(DEFAULT_TOKEN_TTL_HOURS),
         }
     }
 
+    pub fn from(secret_key: String) -> JwtEncoder {
+        JwtEncoder::new(secret_key, Arc::new(RealClock))
+    }
+
+    pub fn with_token_ttl(self, token_ttl: Duration) -> JwtEncoder {
+        JwtEncoder { token_ttl, ..self }
+    }
+
     pub fn create_token(&self, user: &User) -> RealWorldResult<String> {
-        let jwt_payload = JwtPayload::new(user, &self.clock);
+        let jwt_payload = JwtPayload::new(user, self.clock.as_ref(), self.token_ttl);
 
         let encoding_key = EncodingKey::from_secret(&self.secret_key.as_bytes());
 
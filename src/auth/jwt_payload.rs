@@ -14,14 +14,11 @@ pub struct JwtPayload {
 }
 
 impl JwtPayload {
-    pub fn new(user: &User, clock: &Box<dyn Clock>) -> JwtPayload {
-        let now = clock.now().timestamp() as usize;
+    pub fn new(user: &User, clock: &dyn Clock, token_ttl: Duration) -> JwtPayload {
+        let issued_at = clock.now();
+        let now = issued_at.timestamp() as usize;
 
-        let expired_at = clock
-            .now()
-            .checked_add_signed(Duration::hours(3))
-            .unwrap()
-            .timestamp() as usize;
+        let expired_at = issued_at.checked_add_signed(token_ttl).unwrap().timestamp() as usize;
 
         JwtPayload {
             id: user.id(),
@@ -34,14 +31,17 @@ impl JwtPayload {
     pub fn id(&self) -> i64 {
         self.id
     }
+
+    pub fn is_expired(&self, clock: &dyn Clock) -> bool {
+        (self.exp as i64) < clock.now().timestamp()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{
-        auth::clock::{Clock, RealClock},
-        user::domain::user::User,
-    };
+    use chrono::Duration;
+
+    use crate::{auth::clock::RealClock, user::domain::user::User};
 
     use super::JwtPayload;
 
@@ -55,8 +55,7 @@ mod tests {
             None,
             None,
         );
-        let real_clock: Box<dyn Clock> = Box::new(RealClock);
-        let jwt_payload = JwtPayload::new(&user, &real_clock);
+        let jwt_payload = JwtPayload::new(&user, &RealClock, Duration::hours(3));
 
         assert_eq!(jwt_payload.id, 1);
     }
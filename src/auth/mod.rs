@@ -1,4 +1,4 @@
-mod clock;
+pub mod clock;
 pub mod hash_password;
 pub mod jwt_decoder;
 pub mod jwt_encoder;
@@ -0,0 +1,351 @@
+use std::io::{stderr, stdin, stdout, BufRead};
+
+use anyhow::{anyhow, Context};
+use clap::{Parser, Subcommand};
+
+use crate::config::container::AppContainer;
+use crate::config::db::{init_db, DbPool};
+use crate::config::logging::init_logging;
+use crate::config::migrate::{migration_status, revert_migrations, run_migrations, MigrationState};
+use crate::config::settings::Settings;
+use crate::config::RealWorldResult;
+use crate::seed::{SeedOptions, Seeder, SEED_PASSWORD};
+use crate::start_application;
+use crate::user::domain::model::UserRegistry;
+use crate::user::domain::service::DynUserService;
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "RealWorld API server")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (the default when no command is given)
+    Serve,
+    /// Manage the database schema
+    Migrate {
+        #[command(subcommand)]
+        command: MigrateCommand,
+    },
+    /// Register a user; the password is read from stdin when --password is omitted
+    CreateUser {
+        #[arg(long)]
+        username: String,
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Set a new password; it is read from stdin when --password is omitted
+    ResetPassword {
+        email: String,
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Print a JWT for the user, for calling the API locally
+    IssueToken { user_id: i64 },
+    /// Load and validate the settings, then print them with secrets redacted
+    CheckConfig {
+        /// Also try to connect to the configured databases
+        #[arg(long)]
+        connect: bool,
+    },
+    /// Fill the database with reproducible sample data for local development, all or
+    /// nothing
+    Seed {
+        /// RNG seed; the same seed always produces the same data
+        #[arg(long, default_value_t = SeedOptions::default().seed)]
+        seed: u64,
+        #[arg(long, default_value_t = SeedOptions::default().users)]
+        users: u32,
+        #[arg(long, default_value_t = SeedOptions::default().follows_per_user)]
+        follows_per_user: u32,
+        #[arg(long, default_value_t = SeedOptions::default().articles_per_user)]
+        articles_per_user: u32,
+        #[arg(long, default_value_t = SeedOptions::default().favorites_per_user)]
+        favorites_per_user: u32,
+        #[arg(long, default_value_t = SeedOptions::default().comments_per_article)]
+        comments_per_article: u32,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommand {
+    /// Apply every pending migration
+    Up,
+    /// Revert the latest migration, or every migration newer than --target
+    Down {
+        #[arg(long)]
+        target: Option<i64>,
+    },
+    /// List migrations and whether they are applied
+    Status,
+}
+
+pub async fn run(cli: Cli) -> RealWorldResult<()> {
+    let settings = Settings::load()?;
+    let command = cli.command.unwrap_or(Command::Serve);
+
+    // Admin commands print their result on stdout, so their logs go to stderr.
+    let log_guard = match command {
+        Command::Serve => init_logging(&settings.logging, stdout()),
+        _ => init_logging(&settings.logging, stderr()),
+    };
+
+    let result = match command {
+        Command::Serve => start_application(settings).await,
+        Command::Migrate { command } => migrate(settings, command).await,
+        Command::CreateUser {
+            username,
+            email,
+            password,
+        } => create_user(settings, username, email, password).await,
+        Command::ResetPassword { email, password } => {
+            reset_password(settings, email, password).await
+        }
+        Command::IssueToken { user_id } => issue_token(settings, user_id).await,
+        Command::CheckConfig { connect } => check_config(settings, connect).await,
+        Command::Seed {
+            seed: rng_seed,
+            users,
+            follows_per_user,
+            articles_per_user,
+            favorites_per_user,
+            comments_per_article,
+        } => {
+            let options = SeedOptions {
+                seed: rng_seed,
+                users,
+                follows_per_user,
+                articles_per_user,
+                favorites_per_user,
+                comments_per_article,
+            };
+            seed(settings, options).await
+        }
+    };
+
+    drop(log_guard);
+    result
+}
+
+async fn migrate(settings: Settings, command: MigrateCommand) -> RealWorldResult<()> {
+    let db_pool = init_db(&settings.database).await?;
+    let pool = db_pool.primary();
+
+    match command {
+        MigrateCommand::Up => run_migrations(pool).await?,
+        MigrateCommand::Down { target } => {
+            revert_migrations(pool, target).await?;
+        }
+        MigrateCommand::Status => {
+            for status in migration_status(pool).await? {
+                let state = match status.state {
+                    MigrationState::Applied => "applied",
+                    MigrationState::Pending => "pending",
+                    MigrationState::ChecksumMismatch => "checksum mismatch",
+                };
+                println!("{:<16}{:<32}{}", status.version, status.description, state);
+            }
+        }
+    }
+
+    db_pool.close().await;
+    Ok(())
+}
+
+async fn create_user(
+    settings: Settings,
+    username: String,
+    email: String,
+    password: Option<String>,
+) -> RealWorldResult<()> {
+    let password = read_password(password)?;
+    let (db_pool, user_service) = user_service(settings).await?;
+
+    user_service.is_exist(email.clone()).await?;
+    let auth_user = user_service
+        .registry(UserRegistry::new(username, email, password))
+        .await?;
+    println!("Created {} <{}>", auth_user.username(), auth_user.email());
+
+    db_pool.close().await;
+    Ok(())
+}
+
+async fn reset_password(
+    settings: Settings,
+    email: String,
+    password: Option<String>,
+) -> RealWorldResult<()> {
+    let password = read_password(password)?;
+    let (db_pool, user_service) = user_service(settings).await?;
+
+    let auth_user = user_service
+        .reset_password(email.clone(), password)
+        .await
+        .with_context(|| format!("Failed reset password of {}", email))?;
+    println!("Password reset for {}", auth_user.email());
+
+    db_pool.close().await;
+    Ok(())
+}
+
+async fn issue_token(settings: Settings, user_id: i64) -> RealWorldResult<()> {
+    let (db_pool, user_service) = user_service(settings).await?;
+
+    let auth_user = user_service
+        .get_info(user_id)
+        .await
+        .with_context(|| format!("No user with id {}", user_id))?;
+    println!("{}", auth_user.token());
+
+    db_pool.close().await;
+    Ok(())
+}
+
+async fn check_config(settings: Settings, connect: bool) -> RealWorldResult<()> {
+    let database = &settings.database;
+    println!("server.listen_addr        {}", settings.server.listen_addr);
+    println!("metrics.listen_addr       {}", settings.metrics.listen_addr);
+    println!("database.url              {}", redact_url(&database.url));
+    if let Some(replica_url) = &database.replica_url {
+        println!("database.replica_url      {}", redact_url(replica_url));
+    }
+    println!(
+        "database.connections      {}..{}",
+        database.min_connections, database.max_connections
+    );
+    println!("database.auto_migrate     {}", database.auto_migrate);
+    println!("jwt.token_ttl_hours       {}", settings.jwt.token_ttl_hours);
+    println!("logging.format            {:?}", settings.logging.format);
+    println!("logging.filter            {}", settings.logging.filter);
+
+    if connect {
+        let db_pool = init_db(database).await?;
+        sqlx::query("SELECT 1")
+            .execute(db_pool.reader())
+            .await
+            .context("Database query failed")?;
+        println!("Database connection ok");
+        db_pool.close().await;
+    }
+
+    println!("Configuration is valid");
+    Ok(())
+}
+
+async fn seed(settings: Settings, options: SeedOptions) -> RealWorldResult<()> {
+    let container = container(settings).await?;
+    let db_pool = container.db_pool.clone();
+
+    let summary = Seeder::new(container.hash_password, container.clock, db_pool.clone())
+        .run(&options)
+        .await?;
+    println!(
+        "Seeded {} users, {} follows, {} articles, {} favorites and {} comments (seed {}, password \"{}\")",
+        summary.users,
+        summary.follows,
+        summary.articles,
+        summary.favorites,
+        summary.comments,
+        options.seed,
+        SEED_PASSWORD
+    );
+
+    db_pool.close().await;
+    Ok(())
+}
+
+async fn container(settings: Settings) -> RealWorldResult<AppContainer> {
+    let db_pool = init_db(&settings.database).await?;
+    Ok(AppContainer::builder(settings, db_pool).build())
+}
+
+async fn user_service(settings: Settings) -> RealWorldResult<(DbPool, DynUserService)> {
+    let container = container(settings).await?;
+    Ok((container.db_pool, container.user_service))
+}
+
+/// Uses the `--password` value, or the first line of stdin so it stays out of
+/// the shell history and process list.
+fn read_password(password: Option<String>) -> RealWorldResult<String> {
+    let password = match password {
+        Some(password) => password,
+        None => {
+            let mut line = String::new();
+            stdin()
+                .lock()
+                .read_line(&mut line)
+                .context("Failed read password from stdin")?;
+            line.trim_end_matches(['\r', '\n']).to_owned()
+        }
+    };
+
+    if password.len() < MIN_PASSWORD_LENGTH {
+        return Err(anyhow!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        ));
+    }
+    Ok(password)
+}
+
+/// Hides the password of a connection url, both `user:password@` and `?password=`.
+fn redact_url(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return String::from(url);
+    };
+    let (address, query) = match rest.split_once('?') {
+        Some((address, query)) => (address, Some(query)),
+        None => (rest, None),
+    };
+    let address = match address.split_once('@') {
+        Some((credentials, host)) => match credentials.split_once(':') {
+            Some((user, _)) => format!("{}:***@{}", user, host),
+            None => String::from(address),
+        },
+        None => String::from(address),
+    };
+    match query {
+        Some(query) => format!("{}://{}?{}", scheme, address, redact_query(query)),
+        None => format!("{}://{}", scheme, address),
+    }
+}
+
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if name.eq_ignore_ascii_case("password") => format!("{}=***", name),
+            _ => String::from(pair),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::redact_url;
+
+    #[test]
+    fn redact_url_hides_password_test() {
+        assert_eq!(
+            redact_url("postgres://app:secret@db:5432/realworld"),
+            "postgres://app:***@db:5432/realworld"
+        );
+        assert_eq!(
+            redact_url("postgres://postgres@localhost/realworld"),
+            "postgres://postgres@localhost/realworld"
+        );
+        assert_eq!(
+            redact_url("postgres://db/realworld?user=app&password=secret&sslmode=require"),
+            "postgres://db/realworld?user=app&password=***&sslmode=require"
+        );
+    }
+}
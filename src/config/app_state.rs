@@ -1,25 +1,29 @@
-use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use dotenv::dotenv;
-
-use crate::config::db::{init_db, DbPool};
+use crate::config::settings::Settings;
 
 pub type ArcAppState = Arc<AppState>;
 
-#[derive(Debug)]
 pub struct AppState {
-    pub secret_key: String,
+    pub settings: Settings,
+    shutting_down: AtomicBool,
 }
 
 impl AppState {
-    pub fn new(secret_key: String) -> Self {
-        AppState { secret_key }
+    pub fn new(settings: Settings) -> Self {
+        AppState {
+            settings,
+            shutting_down: AtomicBool::new(false),
+        }
     }
-}
 
-pub async fn init_app_state() -> AppState {
-    let secret_key = env::var("SECRET_KEY").expect("Get Secret Key Error");
+    /// Marks the process as draining so readiness checks start failing.
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
 
-    AppState::new(secret_key)
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
 }
@@ -0,0 +1,434 @@
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use chrono::Duration;
+
+use crate::auth::clock::{DynClock, RealClock};
+use crate::auth::hash_password::{ArgonHash, DynHashPassword};
+use crate::auth::jwt_decoder::{ArcJwtDecoder, JwtDecoder};
+use crate::auth::jwt_encoder::{ArcJwtEncoder, JwtEncoder};
+use crate::config::app_state::{AppState, ArcAppState};
+use crate::config::db::DbPool;
+use crate::config::settings::Settings;
+use crate::middleware::rate_limit::store::{DynRateLimitStore, InMemoryRateLimitStore};
+use crate::middleware::rate_limit::{ArcRateLimiter, RateLimiter};
+use crate::profile::domain::repository::DynProfileRepository;
+use crate::profile::domain::service::DynProfileService;
+use crate::profile::repository::repository::ConcreteProfileRepository;
+use crate::profile::service::service::ConcreteProfileService;
+use crate::unit_of_work::cached::CachedUnitOfWork;
+use crate::unit_of_work::postgres::ConcreteUnitOfWork;
+use crate::unit_of_work::DynUnitOfWork;
+use crate::user::domain::repository::DynUserRepository;
+use crate::user::domain::service::DynUserService;
+use crate::user::repository::cache::{DynUserCache, InMemoryUserCache};
+use crate::user::repository::cached::CachedUserRepository;
+use crate::user::repository::repository::ConcreteUserRepository;
+use crate::user::service::service::ConcreteUserService;
+
+/// Every dependency of the application, used as the axum router state. Handlers and
+/// extractors take only the part they need through `FromRef`, so asking for something
+/// the container does not hold fails to compile.
+#[derive(Clone)]
+pub struct AppContainer {
+    pub app_state: ArcAppState,
+    pub db_pool: DbPool,
+    pub clock: DynClock,
+    pub hash_password: DynHashPassword,
+    pub jwt_encoder: ArcJwtEncoder,
+    pub jwt_decoder: ArcJwtDecoder,
+    pub user_repository: DynUserRepository,
+    pub profile_repository: DynProfileRepository,
+    pub unit_of_work: DynUnitOfWork,
+    pub user_service: DynUserService,
+    pub profile_service: DynProfileService,
+    pub rate_limiter: ArcRateLimiter,
+}
+
+impl AppContainer {
+    pub fn builder(settings: Settings, db_pool: DbPool) -> AppContainerBuilder {
+        AppContainerBuilder {
+            components: Components {
+                settings,
+                db_pool,
+                clock: None,
+                hash_password: None,
+                jwt_encoder: None,
+                jwt_decoder: None,
+                user_cache: None,
+                user_service: None,
+                profile_service: None,
+                rate_limit_store: None,
+            },
+            user_repository: Production,
+            profile_repository: Production,
+            unit_of_work: Production,
+        }
+    }
+}
+
+/// A repository or unit of work slot that still holds the production implementation.
+pub struct Production;
+
+/// A repository or unit of work slot that was swapped for `T`.
+pub struct Swapped<T>(T);
+
+/// Resolves a slot to its component, building the production one when not swapped.
+pub trait Slot<T> {
+    fn or_production(self, production: impl FnOnce() -> T) -> T;
+}
+
+impl<T> Slot<T> for Production {
+    fn or_production(self, production: impl FnOnce() -> T) -> T {
+        production()
+    }
+}
+
+impl<T> Slot<T> for Swapped<T> {
+    fn or_production(self, _: impl FnOnce() -> T) -> T {
+        self.0
+    }
+}
+
+/// Builds an `AppContainer` from the settings and database pool. Anything not set
+/// explicitly gets the production implementation, wired to the components that were.
+///
+/// The repository and unit of work slots are tracked in the type: transactions bring
+/// their own repositories, so `build` only exists while the unit of work matches them,
+/// i.e. when nothing was swapped or the unit of work was swapped too.
+///
+/// ```compile_fail
+/// # use std::sync::Arc;
+/// # use real_world_axum_sqlx::config::container::AppContainer;
+/// # use real_world_axum_sqlx::config::db::DbPool;
+/// # use real_world_axum_sqlx::config::settings::Settings;
+/// # use real_world_axum_sqlx::user::repository::memory::InMemoryUserRepository;
+/// fn container(settings: Settings, db_pool: DbPool) -> AppContainer {
+///     AppContainer::builder(settings, db_pool)
+///         .user_repository(Arc::new(InMemoryUserRepository::new()))
+///         .build()
+/// }
+/// ```
+pub struct AppContainerBuilder<U = Production, P = Production, W = Production> {
+    components: Components,
+    user_repository: U,
+    profile_repository: P,
+    unit_of_work: W,
+}
+
+struct Components {
+    settings: Settings,
+    db_pool: DbPool,
+    clock: Option<DynClock>,
+    hash_password: Option<DynHashPassword>,
+    jwt_encoder: Option<ArcJwtEncoder>,
+    jwt_decoder: Option<ArcJwtDecoder>,
+    user_cache: Option<DynUserCache>,
+    user_service: Option<DynUserService>,
+    profile_service: Option<DynProfileService>,
+    rate_limit_store: Option<DynRateLimitStore>,
+}
+
+impl<U, P, W> AppContainerBuilder<U, P, W> {
+    pub fn clock(mut self, clock: DynClock) -> Self {
+        self.components.clock = Some(clock);
+        self
+    }
+
+    pub fn hash_password(mut self, hash_password: DynHashPassword) -> Self {
+        self.components.hash_password = Some(hash_password);
+        self
+    }
+
+    pub fn jwt_encoder(mut self, jwt_encoder: ArcJwtEncoder) -> Self {
+        self.components.jwt_encoder = Some(jwt_encoder);
+        self
+    }
+
+    pub fn jwt_decoder(mut self, jwt_decoder: ArcJwtDecoder) -> Self {
+        self.components.jwt_decoder = Some(jwt_decoder);
+        self
+    }
+
+    pub fn user_repository(
+        self,
+        user_repository: DynUserRepository,
+    ) -> AppContainerBuilder<Swapped<DynUserRepository>, P, W> {
+        AppContainerBuilder {
+            components: self.components,
+            user_repository: Swapped(user_repository),
+            profile_repository: self.profile_repository,
+            unit_of_work: self.unit_of_work,
+        }
+    }
+
+    /// Only used while `cache.enabled` is set.
+    pub fn user_cache(mut self, user_cache: DynUserCache) -> Self {
+        self.components.user_cache = Some(user_cache);
+        self
+    }
+
+    pub fn profile_repository(
+        self,
+        profile_repository: DynProfileRepository,
+    ) -> AppContainerBuilder<U, Swapped<DynProfileRepository>, W> {
+        AppContainerBuilder {
+            components: self.components,
+            user_repository: self.user_repository,
+            profile_repository: Swapped(profile_repository),
+            unit_of_work: self.unit_of_work,
+        }
+    }
+
+    pub fn unit_of_work(
+        self,
+        unit_of_work: DynUnitOfWork,
+    ) -> AppContainerBuilder<U, P, Swapped<DynUnitOfWork>> {
+        AppContainerBuilder {
+            components: self.components,
+            user_repository: self.user_repository,
+            profile_repository: self.profile_repository,
+            unit_of_work: Swapped(unit_of_work),
+        }
+    }
+
+    pub fn user_service(mut self, user_service: DynUserService) -> Self {
+        self.components.user_service = Some(user_service);
+        self
+    }
+
+    pub fn profile_service(mut self, profile_service: DynProfileService) -> Self {
+        self.components.profile_service = Some(profile_service);
+        self
+    }
+
+    pub fn rate_limit_store(mut self, rate_limit_store: DynRateLimitStore) -> Self {
+        self.components.rate_limit_store = Some(rate_limit_store);
+        self
+    }
+}
+
+impl AppContainerBuilder {
+    pub fn build(self) -> AppContainer {
+        self.assemble()
+    }
+}
+
+impl<U, P> AppContainerBuilder<U, P, Swapped<DynUnitOfWork>>
+where
+    U: Slot<DynUserRepository>,
+    P: Slot<DynProfileRepository>,
+{
+    pub fn build(self) -> AppContainer {
+        self.assemble()
+    }
+}
+
+impl<U, P, W> AppContainerBuilder<U, P, W>
+where
+    U: Slot<DynUserRepository>,
+    P: Slot<DynProfileRepository>,
+    W: Slot<DynUnitOfWork>,
+{
+    fn assemble(self) -> AppContainer {
+        let components = self.components;
+        let db_pool = components.db_pool;
+        let jwt_settings = &components.settings.jwt;
+
+        let clock: DynClock = components.clock.unwrap_or_else(|| Arc::new(RealClock));
+        let hash_password: DynHashPassword = components
+            .hash_password
+            .unwrap_or_else(|| Arc::new(ArgonHash));
+        let jwt_encoder = components.jwt_encoder.unwrap_or_else(|| {
+            Arc::new(
+                JwtEncoder::new(jwt_settings.secret_key.clone(), clock.clone())
+                    .with_token_ttl(Duration::hours(jwt_settings.token_ttl_hours)),
+            )
+        });
+        let jwt_decoder = components.jwt_decoder.unwrap_or_else(|| {
+            Arc::new(JwtDecoder::new(
+                jwt_settings.secret_key.clone(),
+                clock.clone(),
+            ))
+        });
+
+        let user_repository: DynUserRepository = self.user_repository.or_production(|| {
+            Arc::new(ConcreteUserRepository::new(db_pool.clone(), clock.clone()))
+        });
+        let cache_settings = &components.settings.cache;
+        let user_cache: Option<DynUserCache> = cache_settings.enabled.then(|| {
+            components.user_cache.unwrap_or_else(|| {
+                Arc::new(InMemoryUserCache::new(
+                    NonZeroUsize::new(cache_settings.capacity).unwrap_or(NonZeroUsize::MIN),
+                    Duration::seconds(cache_settings.ttl_secs as i64),
+                    clock.clone(),
+                ))
+            })
+        });
+        let user_repository: DynUserRepository = match &user_cache {
+            Some(user_cache) => Arc::new(CachedUserRepository::new(
+                user_repository,
+                user_cache.clone(),
+            )),
+            None => user_repository,
+        };
+        let profile_repository: DynProfileRepository =
+            self.profile_repository.or_production(|| {
+                Arc::new(ConcreteProfileRepository::new(
+                    db_pool.clone(),
+                    clock.clone(),
+                ))
+            });
+
+        let unit_of_work: DynUnitOfWork = self
+            .unit_of_work
+            .or_production(|| Arc::new(ConcreteUnitOfWork::new(db_pool.clone(), clock.clone())));
+        let unit_of_work: DynUnitOfWork = match user_cache {
+            Some(user_cache) => Arc::new(CachedUnitOfWork::new(unit_of_work, user_cache)),
+            None => unit_of_work,
+        };
+
+        let user_service: DynUserService = components.user_service.unwrap_or_else(|| {
+            Arc::new(ConcreteUserService::new(
+                user_repository.clone(),
+                hash_password.clone(),
+                jwt_encoder.clone(),
+                unit_of_work.clone(),
+            ))
+        });
+        let profile_service: DynProfileService = components.profile_service.unwrap_or_else(|| {
+            Arc::new(ConcreteProfileService::new(
+                profile_repository.clone(),
+                user_service.clone(),
+                unit_of_work.clone(),
+            ))
+        });
+
+        let rate_limit_store = components
+            .rate_limit_store
+            .unwrap_or_else(|| Arc::new(InMemoryRateLimitStore::new()));
+        let rate_limiter = Arc::new(RateLimiter::new(
+            components.settings.rate_limit.clone(),
+            rate_limit_store,
+            jwt_decoder.clone(),
+            clock.clone(),
+        ));
+
+        AppContainer {
+            app_state: Arc::new(AppState::new(components.settings)),
+            db_pool,
+            clock,
+            hash_password,
+            jwt_encoder,
+            jwt_decoder,
+            user_repository,
+            profile_repository,
+            unit_of_work,
+            user_service,
+            profile_service,
+            rate_limiter,
+        }
+    }
+}
+
+impl FromRef<AppContainer> for ArcAppState {
+    fn from_ref(container: &AppContainer) -> Self {
+        container.app_state.clone()
+    }
+}
+
+impl FromRef<AppContainer> for DbPool {
+    fn from_ref(container: &AppContainer) -> Self {
+        container.db_pool.clone()
+    }
+}
+
+impl FromRef<AppContainer> for DynClock {
+    fn from_ref(container: &AppContainer) -> Self {
+        container.clock.clone()
+    }
+}
+
+impl FromRef<AppContainer> for ArcJwtDecoder {
+    fn from_ref(container: &AppContainer) -> Self {
+        container.jwt_decoder.clone()
+    }
+}
+
+impl FromRef<AppContainer> for DynUserService {
+    fn from_ref(container: &AppContainer) -> Self {
+        container.user_service.clone()
+    }
+}
+
+impl FromRef<AppContainer> for DynProfileService {
+    fn from_ref(container: &AppContainer) -> Self {
+        container.profile_service.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{TimeZone, Utc};
+    use sqlx::postgres::PgPoolOptions;
+
+    use crate::auth::clock::MockClock;
+    use crate::config::db::DbPool;
+    use crate::config::settings::Settings;
+    use crate::profile::repository::memory::InMemoryProfileRepository;
+    use crate::unit_of_work::memory::InMemoryUnitOfWork;
+    use crate::user::domain::model::UserRegistry;
+    use crate::user::repository::memory::InMemoryUserRepository;
+
+    use super::AppContainer;
+
+    const SETTINGS: &str = r#"
+[database]
+url = "postgres://unused/realworld"
+
+[jwt]
+secret_key = "container-test-secret"
+"#;
+
+    #[tokio::test]
+    async fn builder_wires_swapped_components_test() {
+        let settings = Settings::from_toml(SETTINGS).unwrap();
+        let pool = PgPoolOptions::new()
+            .connect_lazy(&settings.database.url)
+            .unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let users = Arc::new(InMemoryUserRepository::new());
+        let profiles = Arc::new(InMemoryProfileRepository::new());
+        let container = AppContainer::builder(settings, DbPool::new(pool))
+            .clock(Arc::new(MockClock::new(now)))
+            .user_repository(users.clone())
+            .profile_repository(profiles.clone())
+            .unit_of_work(Arc::new(InMemoryUnitOfWork::new(users, profiles)))
+            .build();
+
+        let auth_user = container
+            .user_service
+            .registry(UserRegistry::new(
+                String::from("jake"),
+                String::from("jake@jake.jake"),
+                String::from("password1234"),
+            ))
+            .await
+            .unwrap();
+        let payload = container
+            .jwt_decoder
+            .decode_token(auth_user.token())
+            .unwrap();
+        let user = container
+            .user_repository
+            .find_by_username(String::from("jake"))
+            .await
+            .unwrap();
+
+        assert_eq!(container.clock.now(), now);
+        assert_eq!(payload.id(), user.id());
+    }
+}
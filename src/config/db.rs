@@ -1,13 +1,390 @@
-use anyhow::Context;
-use sqlx::postgres::PgPoolOptions;
-use sqlx::Postgres;
+use std::cell::Cell;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-pub type DbPool = sqlx::Pool<Postgres>;
+use anyhow::{anyhow, Context};
+use metrics::{counter, histogram};
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{PgConnection, Postgres};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tokio::time::sleep;
+use tracing::{info, warn};
 
-pub async fn init_db(database_url: &str) -> DbPool {
-    PgPoolOptions::new()
-        .connect(database_url)
+use crate::config::metrics::{DB_POOL_ACQUIRE_DURATION_SECONDS, DB_POOL_ACQUIRE_TIMEOUTS_TOTAL};
+use crate::config::settings::DatabaseSettings;
+use crate::config::RealWorldResult;
+
+pub type PgPool = sqlx::Pool<Postgres>;
+pub type PgTransaction = sqlx::Transaction<'static, Postgres>;
+/// Transaction shared by the repositories of one unit of work, `None` once it ended.
+pub type SharedTransaction = Arc<Mutex<Option<PgTransaction>>>;
+
+tokio::task_local! {
+    static PRIMARY_PIN: PrimaryPin;
+}
+
+/// Per-request routing state, see [`track_writes`].
+struct PrimaryPin {
+    reads: Cell<bool>,
+    wrote: Cell<bool>,
+}
+
+/// Primary pool for writes plus an optional replica pool for read-only queries.
+#[derive(Debug, Clone)]
+pub struct DbPool {
+    primary: PgPool,
+    replica: Option<PgPool>,
+    read_your_writes: bool,
+}
+
+impl DbPool {
+    pub fn new(primary: PgPool) -> Self {
+        Self {
+            primary,
+            replica: None,
+            read_your_writes: true,
+        }
+    }
+
+    pub fn with_replica(mut self, replica: PgPool, read_your_writes: bool) -> Self {
+        self.replica = Some(replica);
+        self.read_your_writes = read_your_writes;
+        self
+    }
+
+    /// Pool for statements that modify data. Inside [`track_writes`] this also pins
+    /// the rest of the request's reads to the primary.
+    pub fn writer(&self) -> &PgPool {
+        let _ = PRIMARY_PIN.try_with(|pin| {
+            pin.reads.set(true);
+            pin.wrote.set(true);
+        });
+        &self.primary
+    }
+
+    /// Pool for read-only queries: the replica when there is one, unless this
+    /// request is pinned to the primary and `read_your_writes` is on.
+    pub fn reader(&self) -> &PgPool {
+        self.read_pool().0
+    }
+
+    /// Checks out a connection for read-only queries, see [`DbPool::reader`].
+    pub async fn acquire_reader(&self) -> RealWorldResult<PoolConnection<Postgres>> {
+        let (pool, role) = self.read_pool();
+        timed_acquire(role, pool.acquire()).await
+    }
+
+    /// Checks out a connection for statements that modify data, see [`DbPool::writer`].
+    pub async fn acquire_writer(&self) -> RealWorldResult<PoolConnection<Postgres>> {
+        timed_acquire("primary", self.writer().acquire()).await
+    }
+
+    /// Starts a transaction on the primary. The recorded wait includes the `BEGIN`.
+    pub async fn begin(&self) -> RealWorldResult<PgTransaction> {
+        timed_acquire("primary", self.writer().begin()).await
+    }
+
+    fn read_pool(&self) -> (&PgPool, &'static str) {
+        match &self.replica {
+            Some(replica) if !(self.read_your_writes && reads_pinned()) => (replica, "replica"),
+            _ => (&self.primary, "primary"),
+        }
+    }
+
+    pub fn primary(&self) -> &PgPool {
+        &self.primary
+    }
+
+    pub fn replica(&self) -> Option<&PgPool> {
+        self.replica.as_ref()
+    }
+
+    pub async fn close(&self) {
+        self.primary.close().await;
+        if let Some(replica) = &self.replica {
+            replica.close().await;
+        }
+    }
+}
+
+/// Where a repository runs its statements: straight on the pools, or inside a
+/// transaction it shares with other repositories.
+#[derive(Clone)]
+pub enum DbScope {
+    Pool(DbPool),
+    Transaction(SharedTransaction),
+}
+
+impl DbScope {
+    /// Connection for read-only queries, see [`DbPool::reader`].
+    pub async fn reader(&self) -> RealWorldResult<DbConnection> {
+        match self {
+            DbScope::Pool(db_pool) => Ok(DbConnection::Pooled(Box::new(
+                db_pool.acquire_reader().await?,
+            ))),
+            DbScope::Transaction(transaction) => Self::join(transaction).await,
+        }
+    }
+
+    /// Connection for statements that modify data, see [`DbPool::writer`].
+    pub async fn writer(&self) -> RealWorldResult<DbConnection> {
+        match self {
+            DbScope::Pool(db_pool) => Ok(DbConnection::Pooled(Box::new(
+                db_pool.acquire_writer().await?,
+            ))),
+            DbScope::Transaction(transaction) => Self::join(transaction).await,
+        }
+    }
+
+    async fn join(transaction: &SharedTransaction) -> RealWorldResult<DbConnection> {
+        let guard = transaction.clone().lock_owned().await;
+        if guard.is_none() {
+            return Err(anyhow!("Transaction already committed or rolled back"));
+        }
+        Ok(DbConnection::Transaction(guard))
+    }
+}
+
+/// Connection handed out by [`DbScope`]. A transaction is held by one repository
+/// call at a time.
+pub enum DbConnection {
+    Pooled(Box<PoolConnection<Postgres>>),
+    Transaction(OwnedMutexGuard<Option<PgTransaction>>),
+}
+
+impl Deref for DbConnection {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        match self {
+            DbConnection::Pooled(connection) => connection,
+            DbConnection::Transaction(guard) => guard.as_deref().expect("checked by DbScope"),
+        }
+    }
+}
+
+impl DerefMut for DbConnection {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        match self {
+            DbConnection::Pooled(connection) => connection,
+            DbConnection::Transaction(guard) => guard.as_deref_mut().expect("checked by DbScope"),
+        }
+    }
+}
+
+/// Runs `future` with its own write marker, so [`DbPool::reader`] can tell whether
+/// it already wrote to the primary. With `pinned` its reads go to the primary from
+/// the start, e.g. because the client wrote shortly before. Also returns whether
+/// `future` wrote.
+pub async fn track_writes<F: Future>(pinned: bool, future: F) -> (F::Output, bool) {
+    let pin = PrimaryPin {
+        reads: Cell::new(pinned),
+        wrote: Cell::new(false),
+    };
+    PRIMARY_PIN
+        .scope(pin, async {
+            let output = future.await;
+            (output, PRIMARY_PIN.with(|pin| pin.wrote.get()))
+        })
+        .await
+}
+
+fn reads_pinned() -> bool {
+    PRIMARY_PIN.try_with(|pin| pin.reads.get()).unwrap_or(false)
+}
+
+/// Records how long the caller waited for a connection of the `role` pool, and
+/// counts the waits that ran into `acquire_timeout_secs`.
+async fn timed_acquire<T>(
+    role: &'static str,
+    acquire: impl Future<Output = Result<T, sqlx::Error>>,
+) -> RealWorldResult<T> {
+    let started_at = Instant::now();
+    let result = acquire.await;
+
+    histogram!(DB_POOL_ACQUIRE_DURATION_SECONDS, "pool" => role).record(started_at.elapsed());
+    if let Err(sqlx::Error::PoolTimedOut) = result {
+        counter!(DB_POOL_ACQUIRE_TIMEOUTS_TOTAL, "pool" => role).increment(1);
+    }
+    Ok(result?)
+}
+
+pub async fn init_db(settings: &DatabaseSettings) -> RealWorldResult<DbPool> {
+    let primary = connect_pool(&settings.url, settings)
         .await
-        .context("Db Init Error")
-        .unwrap()
+        .context("Primary database")?;
+    let db_pool = DbPool::new(primary);
+
+    match &settings.replica_url {
+        Some(replica_url) => {
+            let replica = connect_pool(replica_url, settings)
+                .await
+                .context("Replica database")?;
+            info!("Read-only queries are routed to the replica database");
+            Ok(db_pool.with_replica(replica, settings.read_your_writes))
+        }
+        None => Ok(db_pool),
+    }
+}
+
+async fn connect_pool(url: &str, settings: &DatabaseSettings) -> RealWorldResult<PgPool> {
+    let mut connect_options = PgConnectOptions::from_str(url)
+        .context("Invalid database url")?
+        .application_name(&settings.application_name);
+    if let Some(statement_timeout_ms) = settings.statement_timeout_ms {
+        connect_options = connect_options.options([("statement_timeout", statement_timeout_ms)]);
+    }
+
+    let pool_options = PgPoolOptions::new()
+        .max_connections(settings.max_connections)
+        .min_connections(settings.min_connections)
+        .acquire_timeout(Duration::from_secs(settings.acquire_timeout_secs))
+        .idle_timeout(disabled_at_zero(settings.idle_timeout_secs))
+        .max_lifetime(disabled_at_zero(settings.max_lifetime_secs));
+
+    if settings.lazy_connect {
+        info!("Database pool created lazily, connections open on first use");
+        return Ok(pool_options.connect_lazy_with(connect_options));
+    }
+
+    connect_with_retry(pool_options, connect_options, settings).await
+}
+
+fn disabled_at_zero(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Retries the initial connection with exponential backoff so a Postgres that is
+/// still starting up does not take the application down with it.
+async fn connect_with_retry(
+    pool_options: PgPoolOptions,
+    connect_options: PgConnectOptions,
+    settings: &DatabaseSettings,
+) -> RealWorldResult<PgPool> {
+    let max_backoff = Duration::from_millis(settings.connect_backoff_max_ms);
+    let mut backoff = Duration::from_millis(settings.connect_backoff_initial_ms);
+    let mut attempt = 1;
+
+    loop {
+        let result = pool_options
+            .clone()
+            .connect_with(connect_options.clone())
+            .await;
+
+        match result {
+            Ok(db_pool) => return Ok(db_pool),
+            Err(err) if attempt < settings.connect_attempts => {
+                warn!(
+                    "Database connection attempt {}/{} failed, retrying in {:?}: {}",
+                    attempt, settings.connect_attempts, backoff, err
+                );
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(max_backoff);
+                attempt += 1;
+            }
+            Err(err) => {
+                return Err(
+                    anyhow!(err).context(format!("Db Init Error after {} attempts", attempt))
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::settings::Settings;
+
+    use super::{init_db, track_writes, PgPool};
+
+    /// Nothing listens on port 1, so connecting fails fast.
+    const UNREACHABLE_DATABASE: &str = r#"
+        [database]
+        url = "postgres://postgres@127.0.0.1:1/realworld"
+        max_connections = 1
+        acquire_timeout_secs = 1
+        connect_attempts = 2
+        connect_backoff_initial_ms = 10
+        connect_backoff_max_ms = 10
+        "#;
+
+    #[tokio::test]
+    async fn give_up_after_connect_attempts_test() {
+        let settings = Settings::parse_toml(UNREACHABLE_DATABASE).unwrap();
+
+        let result = init_db(&settings.database).await;
+
+        let err = result.unwrap_err();
+        assert!(format!("{err:#}").contains("after 2 attempts"));
+    }
+
+    #[tokio::test]
+    async fn lazy_connect_does_not_touch_database_test() {
+        let mut settings = Settings::parse_toml(UNREACHABLE_DATABASE).unwrap();
+        settings.database.lazy_connect = true;
+
+        let result = init_db(&settings.database).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn zero_pool_timeouts_are_disabled_test() {
+        let mut settings = Settings::parse_toml(UNREACHABLE_DATABASE).unwrap().database;
+        settings.lazy_connect = true;
+        settings.idle_timeout_secs = 0;
+
+        let db_pool = init_db(&settings).await.unwrap();
+
+        let options = db_pool.primary().options();
+        assert_eq!(options.get_idle_timeout(), None);
+        assert!(options.get_max_lifetime().is_some());
+    }
+
+    fn is_replica(pool: &PgPool) -> bool {
+        pool.options().get_max_connections() == 2
+    }
+
+    #[tokio::test]
+    async fn reads_go_to_replica_until_request_writes_test() {
+        let mut settings = Settings::parse_toml(UNREACHABLE_DATABASE).unwrap().database;
+        settings.lazy_connect = true;
+        let primary = init_db(&settings).await.unwrap();
+        settings.max_connections = 2;
+        let replica = init_db(&settings).await.unwrap();
+        let db_pool = primary.with_replica(replica.primary().clone(), true);
+
+        let (_, wrote) = track_writes(false, async {
+            assert!(is_replica(db_pool.reader()));
+            assert!(!is_replica(db_pool.writer()));
+            assert!(!is_replica(db_pool.reader()));
+        })
+        .await;
+        track_writes(false, async { assert!(is_replica(db_pool.reader())) }).await;
+        let (_, pinned_wrote) =
+            track_writes(true, async { assert!(!is_replica(db_pool.reader())) }).await;
+
+        assert!(wrote);
+        assert!(!pinned_wrote);
+    }
+
+    #[tokio::test]
+    async fn reads_stay_on_replica_without_read_your_writes_test() {
+        let mut settings = Settings::parse_toml(UNREACHABLE_DATABASE).unwrap().database;
+        settings.lazy_connect = true;
+        let primary = init_db(&settings).await.unwrap();
+        settings.max_connections = 2;
+        let replica = init_db(&settings).await.unwrap();
+        let db_pool = primary.with_replica(replica.primary().clone(), false);
+
+        track_writes(true, async {
+            db_pool.writer();
+            assert!(is_replica(db_pool.reader()));
+        })
+        .await;
+    }
 }
@@ -1,15 +1,28 @@
-use axum::http::StatusCode;
+use std::collections::BTreeMap;
+
+use axum::extract::State;
+use axum::http::{header, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tokio::sync::watch::error;
-use validator::ValidationErrors;
+use utoipa::ToSchema;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+use crate::middleware::request_id::current_request_id;
 
 const BAD_REQUEST: u16 = 40000;
 const UNAUTHORIZED_ERROR_CODE: u16 = 40001;
 const VALIDATE_ERROR_CODE: u16 = 40002;
 const FORBIDDEN_ERROR_CODE: u16 = 40003;
+const MALFORMED_BODY_ERROR_CODE: u16 = 40004;
+const INVALID_PARAMETER_ERROR_CODE: u16 = 40005;
+const REQUEST_TIMEOUT_ERROR_CODE: u16 = 40800;
+const PRECONDITION_FAILED_ERROR_CODE: u16 = 41200;
+const PAYLOAD_TOO_LARGE_ERROR_CODE: u16 = 41300;
+const UNSUPPORTED_MEDIA_TYPE_ERROR_CODE: u16 = 41500;
+const TOO_MANY_REQUESTS_ERROR_CODE: u16 = 42900;
+const INVALID_FIELD_ERROR_CODE: u16 = 42200;
 const INTERNAL_SERVER_ERROR: u16 = 50000;
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -21,13 +34,67 @@ pub enum AppError {
     Forbidden,
 
     #[error(transparent)]
-    AnyHow(#[from] anyhow::Error),
+    AnyHow(anyhow::Error),
 
     #[error(transparent)]
     ValidateError(#[from] ValidationErrors),
 
-    #[error("Missing field `{0}`")]
-    MissingFieldError(String),
+    /// Return `422 Unprocessable Entity`
+    #[error("Missing field `{path}` at line {line}, column {column}")]
+    MissingFieldError {
+        path: String,
+        line: usize,
+        column: usize,
+    },
+
+    /// Return `422 Unprocessable Entity`
+    #[error("Invalid value for `{path}` at line {line}, column {column}: {message}")]
+    InvalidFieldError {
+        path: String,
+        message: String,
+        line: usize,
+        column: usize,
+    },
+
+    /// Return `400 Bad Request`
+    #[error("Malformed JSON at line {line}, column {column}: {message}")]
+    MalformedJsonError {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+
+    /// Return `422 Unprocessable Entity` when serde does not say where the body is wrong
+    #[error("Invalid request body: {message}")]
+    InvalidBodyError { message: String },
+
+    /// Return `400 Bad Request` when serde does not say where the JSON is malformed
+    #[error("Malformed JSON: {message}")]
+    MalformedBodyError { message: String },
+
+    /// Return `400 Bad Request` for an unusable query string or path parameter
+    #[error("Invalid parameter `{path}`: {message}")]
+    InvalidParameterError { path: String, message: String },
+
+    /// Return `408 Request Timeout`
+    #[error("Request took too long")]
+    RequestTimeout,
+
+    /// Return `412 Precondition Failed` when `If-Match` names an outdated version
+    #[error("Resource was modified since it was read, fetch it again")]
+    PreconditionFailed,
+
+    /// Return `413 Payload Too Large`
+    #[error("Request body is too large")]
+    PayloadTooLarge,
+
+    /// Return `415 Unsupported Media Type`
+    #[error("Expected request with `Content-Type: application/json`")]
+    UnsupportedMediaType,
+
+    /// Return `429 Too Many Requests`
+    #[error("Too many requests, slow down")]
+    TooManyRequests,
 
     #[error("Internal Server Error")]
     InternalServerError,
@@ -40,7 +107,17 @@ impl AppError {
             AppError::Forbidden => StatusCode::FORBIDDEN,
             AppError::AnyHow(_) => StatusCode::BAD_REQUEST,
             AppError::ValidateError(_) => StatusCode::BAD_REQUEST,
-            AppError::MissingFieldError(_) => StatusCode::BAD_REQUEST,
+            AppError::MissingFieldError { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::InvalidFieldError { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::MalformedJsonError { .. } => StatusCode::BAD_REQUEST,
+            AppError::InvalidBodyError { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::MalformedBodyError { .. } => StatusCode::BAD_REQUEST,
+            AppError::InvalidParameterError { .. } => StatusCode::BAD_REQUEST,
+            AppError::RequestTimeout => StatusCode::REQUEST_TIMEOUT,
+            AppError::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            AppError::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
             AppError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -51,22 +128,67 @@ impl AppError {
             AppError::Forbidden => FORBIDDEN_ERROR_CODE,
             AppError::AnyHow(_) => BAD_REQUEST,
             AppError::ValidateError(_) => VALIDATE_ERROR_CODE,
-            AppError::MissingFieldError(_) => BAD_REQUEST,
+            AppError::MissingFieldError { .. } => INVALID_FIELD_ERROR_CODE,
+            AppError::InvalidFieldError { .. } => INVALID_FIELD_ERROR_CODE,
+            AppError::MalformedJsonError { .. } => MALFORMED_BODY_ERROR_CODE,
+            AppError::InvalidBodyError { .. } => INVALID_FIELD_ERROR_CODE,
+            AppError::MalformedBodyError { .. } => MALFORMED_BODY_ERROR_CODE,
+            AppError::InvalidParameterError { .. } => INVALID_PARAMETER_ERROR_CODE,
+            AppError::RequestTimeout => REQUEST_TIMEOUT_ERROR_CODE,
+            AppError::PreconditionFailed => PRECONDITION_FAILED_ERROR_CODE,
+            AppError::PayloadTooLarge => PAYLOAD_TOO_LARGE_ERROR_CODE,
+            AppError::UnsupportedMediaType => UNSUPPORTED_MEDIA_TYPE_ERROR_CODE,
+            AppError::TooManyRequests => TOO_MANY_REQUESTS_ERROR_CODE,
             AppError::InternalServerError => INTERNAL_SERVER_ERROR,
         }
     }
+
+    fn field_errors(&self) -> BTreeMap<String, Vec<String>> {
+        let mut field_errors = BTreeMap::new();
+        match self {
+            AppError::ValidateError(errors) => {
+                collect_field_errors(None, errors, &mut field_errors)
+            }
+            AppError::MissingFieldError { path, .. } => {
+                field_errors.insert(path.to_owned(), vec![String::from("is missing")]);
+            }
+            AppError::InvalidFieldError { path, message, .. }
+            | AppError::InvalidParameterError { path, message } => {
+                field_errors.insert(path.to_owned(), vec![message.to_owned()]);
+            }
+            _ => {
+                field_errors.insert(String::from("body"), vec![self.to_string()]);
+            }
+        }
+        field_errors
+    }
+}
+
+/// Services return `anyhow` errors; one that started out as an `AppError` keeps its
+/// status instead of becoming a `400`.
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        err.downcast().unwrap_or_else(AppError::AnyHow)
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (self.status_code(), Json(ErrorResponse::new(&self))).into_response()
+        let mut response = (self.status_code(), Json(ErrorResponse::new(&self))).into_response();
+        response
+            .extensions_mut()
+            .insert(RealWorldErrorResponse::new(&self));
+        response
     }
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
+/// Error body in the default `ErrorFormat`.
+#[derive(Serialize, ToSchema)]
+pub struct ErrorResponse {
     code: u16,
     message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
 }
 
 impl ErrorResponse {
@@ -74,10 +196,171 @@ impl ErrorResponse {
         ErrorResponse {
             code: app_error.error_code(),
             message: app_error.to_string(),
+            request_id: current_request_id(),
+        }
+    }
+}
+
+/// Shape of the JSON body returned for an `AppError`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorFormat {
+    /// `{"code": 40002, "message": "..."}`
+    #[default]
+    Default,
+    /// `{"errors": {"field": ["message"]}}` as described by the RealWorld spec.
+    RealWorld,
+}
+
+/// Error body in the `realworld` `ErrorFormat`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RealWorldErrorResponse {
+    #[serde(skip)]
+    status: StatusCode,
+    errors: BTreeMap<String, Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+impl RealWorldErrorResponse {
+    fn new(app_error: &AppError) -> Self {
+        let status = match app_error {
+            AppError::ValidateError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            _ => app_error.status_code(),
+        };
+        RealWorldErrorResponse {
+            status,
+            errors: app_error.field_errors(),
+            request_id: current_request_id(),
         }
     }
 }
 
-pub async fn error_handler(err: AppError) -> impl IntoResponse {
-    err.into_response()
+/// Whether the response was produced by `AppError::into_response`.
+pub fn is_app_error_response(response: &Response) -> bool {
+    response
+        .extensions()
+        .get::<RealWorldErrorResponse>()
+        .is_some()
+}
+
+/// Rewrites `AppError` responses into the body selected by `ErrorFormat`.
+pub async fn render_error_format(
+    State(format): State<ErrorFormat>,
+    response: Response,
+) -> Response {
+    if format != ErrorFormat::RealWorld {
+        return response;
+    }
+
+    match response
+        .extensions()
+        .get::<RealWorldErrorResponse>()
+        .cloned()
+    {
+        Some(error_response) => {
+            // Keep the headers inner layers set on the error, e.g. `Retry-After`.
+            let (mut parts, _) = response.into_parts();
+            let rendered = (error_response.status, Json(error_response)).into_response();
+            parts.status = rendered.status();
+            parts.headers.remove(header::CONTENT_LENGTH);
+            parts.headers.extend(rendered.headers().clone());
+            Response::from_parts(parts, rendered.into_body())
+        }
+        None => response,
+    }
+}
+
+fn collect_field_errors(
+    prefix: Option<&str>,
+    errors: &ValidationErrors,
+    field_errors: &mut BTreeMap<String, Vec<String>>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            Some(prefix) => format!("{prefix}.{field}"),
+            None => field.to_string(),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let messages = errors.iter().map(|error| match &error.message {
+                    Some(message) => message.to_string(),
+                    None => error.code.to_string(),
+                });
+                field_errors.entry(path).or_default().extend(messages);
+            }
+            ValidationErrorsKind::Struct(errors) => {
+                collect_field_errors(Some(&path), errors, field_errors)
+            }
+            ValidationErrorsKind::List(errors) => {
+                for (index, errors) in errors {
+                    collect_field_errors(Some(&format!("{path}[{index}]")), errors, field_errors);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use validator::Validate;
+    use validator_derive::Validate;
+
+    use super::{AppError, RealWorldErrorResponse};
+
+    #[derive(Validate)]
+    struct Inner {
+        #[validate(length(min = 1, message = "Email is required."))]
+        email: String,
+    }
+
+    #[derive(Validate)]
+    struct Outer {
+        #[validate(nested)]
+        user: Inner,
+    }
+
+    #[test]
+    fn nested_field_errors_test() {
+        let outer = Outer {
+            user: Inner {
+                email: String::new(),
+            },
+        };
+        let errors = outer.validate().unwrap_err();
+
+        let response = RealWorldErrorResponse::new(&AppError::ValidateError(errors));
+
+        assert_eq!(response.status.as_u16(), 422);
+        assert_eq!(
+            response.errors.get("user.email"),
+            Some(&vec![String::from("Email is required.")])
+        );
+    }
+
+    #[test]
+    fn non_validation_error_uses_body_key_test() {
+        let response = RealWorldErrorResponse::new(&AppError::Unauthorized);
+
+        assert_eq!(response.status.as_u16(), 401);
+        assert_eq!(
+            response.errors.get("body"),
+            Some(&vec![String::from("authentication required")])
+        );
+    }
+
+    #[test]
+    fn unlocated_body_error_has_no_position_test() {
+        let error = AppError::InvalidBodyError {
+            message: String::from("expected a map"),
+        };
+        let response = RealWorldErrorResponse::new(&error);
+
+        assert_eq!(response.status.as_u16(), 422);
+        assert_eq!(
+            response.errors.get("body"),
+            Some(&vec![String::from("Invalid request body: expected a map")])
+        );
+    }
 }
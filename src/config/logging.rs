@@ -0,0 +1,48 @@
+use std::io::Write;
+
+use serde::Deserialize;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::settings::LoggingSettings;
+
+/// Output style of the log lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+/// Installs the global tracing subscriber writing to `writer`.
+///
+/// `logging.format` selects `pretty` or `json` output and `logging.filter` takes
+/// per-module filters such as `real_world_axum_sqlx::user=debug,sqlx::query=warn`.
+/// Closed spans are logged with their busy/idle time, which times every instrumented
+/// repository call. Keep the returned guard alive until shutdown so buffered lines
+/// get flushed.
+pub fn init_logging<W>(settings: &LoggingSettings, writer: W) -> WorkerGuard
+where
+    W: Write + Send + 'static,
+{
+    let filter = EnvFilter::new(&settings.filter);
+    let (writer, guard) = tracing_appender::non_blocking(writer);
+
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(writer);
+
+    match settings.format {
+        LogFormat::Pretty => subscriber.pretty().init(),
+        LogFormat::Json => subscriber
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .init(),
+    }
+
+    guard
+}
@@ -0,0 +1,107 @@
+use axum::extract::State;
+use axum::routing::get;
+use axum::Router;
+use metrics::{describe_counter, describe_gauge, describe_histogram, gauge};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use crate::config::db::{DbPool, PgPool};
+
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+pub const USER_REGISTRATIONS_TOTAL: &str = "realworld_user_registrations_total";
+pub const USER_LOGINS_TOTAL: &str = "realworld_user_logins_total";
+pub const PROFILE_FOLLOWS_TOTAL: &str = "realworld_profile_follows_total";
+pub const PROFILE_UNFOLLOWS_TOTAL: &str = "realworld_profile_unfollows_total";
+pub const USER_CACHE_REQUESTS_TOTAL: &str = "realworld_user_cache_requests_total";
+pub const DB_POOL_ACQUIRE_DURATION_SECONDS: &str = "db_pool_acquire_duration_seconds";
+pub const DB_POOL_ACQUIRE_TIMEOUTS_TOTAL: &str = "db_pool_acquire_timeouts_total";
+
+const DB_POOL_CONNECTIONS: &str = "db_pool_connections";
+const DB_POOL_IDLE_CONNECTIONS: &str = "db_pool_idle_connections";
+const DB_POOL_IN_USE_CONNECTIONS: &str = "db_pool_in_use_connections";
+const DB_POOL_MAX_CONNECTIONS: &str = "db_pool_max_connections";
+
+const HTTP_DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+const DB_ACQUIRE_BUCKETS: &[f64] = &[0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 30.0];
+
+/// Installs the global Prometheus recorder. Call once at startup.
+pub fn init_metrics() -> PrometheusHandle {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()),
+            HTTP_DURATION_BUCKETS,
+        )
+        .expect("http duration buckets are not empty")
+        .set_buckets_for_metric(
+            Matcher::Full(DB_POOL_ACQUIRE_DURATION_SECONDS.to_string()),
+            DB_ACQUIRE_BUCKETS,
+        )
+        .expect("db acquire buckets are not empty")
+        .install_recorder()
+        .expect("Failed install metrics recorder");
+
+    describe_counter!(
+        HTTP_REQUESTS_TOTAL,
+        "HTTP requests by matched route and status"
+    );
+    describe_histogram!(
+        HTTP_REQUEST_DURATION_SECONDS,
+        "HTTP request latency by matched route and status"
+    );
+    describe_counter!(USER_REGISTRATIONS_TOTAL, "Users signed up");
+    describe_counter!(USER_LOGINS_TOTAL, "Login attempts by result");
+    describe_counter!(
+        PROFILE_FOLLOWS_TOTAL,
+        "Follow requests that created a follow"
+    );
+    describe_counter!(PROFILE_UNFOLLOWS_TOTAL, "Unfollow requests");
+    describe_counter!(
+        USER_CACHE_REQUESTS_TOTAL,
+        "User lookups by id or username, by cache hit or miss"
+    );
+    describe_gauge!(DB_POOL_CONNECTIONS, "Open connections in the pool");
+    describe_gauge!(DB_POOL_IDLE_CONNECTIONS, "Idle connections in the pool");
+    describe_gauge!(
+        DB_POOL_IN_USE_CONNECTIONS,
+        "Connections checked out of the pool"
+    );
+    describe_gauge!(DB_POOL_MAX_CONNECTIONS, "Configured pool size limit");
+    describe_histogram!(
+        DB_POOL_ACQUIRE_DURATION_SECONDS,
+        "Time spent waiting for a pool connection"
+    );
+    describe_counter!(
+        DB_POOL_ACQUIRE_TIMEOUTS_TOTAL,
+        "Connection checkouts that gave up after the acquire timeout"
+    );
+
+    handle
+}
+
+/// Router for the metrics listener, kept off the public API port.
+pub fn metrics_route(handle: PrometheusHandle, db_pool: DbPool) -> Router {
+    Router::new()
+        .route("/metrics", get(render_metrics))
+        .with_state((handle, db_pool))
+}
+
+async fn render_metrics(State((handle, db_pool)): State<(PrometheusHandle, DbPool)>) -> String {
+    record_pool_gauges(db_pool.primary(), "primary");
+    if let Some(replica) = db_pool.replica() {
+        record_pool_gauges(replica, "replica");
+    }
+
+    handle.render()
+}
+
+fn record_pool_gauges(pool: &PgPool, role: &'static str) {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+
+    gauge!(DB_POOL_CONNECTIONS, "pool" => role).set(size);
+    gauge!(DB_POOL_IDLE_CONNECTIONS, "pool" => role).set(idle);
+    gauge!(DB_POOL_IN_USE_CONNECTIONS, "pool" => role).set(size.saturating_sub(idle));
+    gauge!(DB_POOL_MAX_CONNECTIONS, "pool" => role).set(pool.options().get_max_connections());
+}
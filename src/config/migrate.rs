@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use sqlx::migrate::{Migrate, Migrator};
+use tracing::info;
+
+use crate::config::db::PgPool;
+use crate::config::RealWorldResult;
+
+/// Migrations under `migrations/`, compiled into the binary.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the file changed since; migrations must never be edited once shipped.
+    ChecksumMismatch,
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+pub async fn run_migrations(pool: &PgPool) -> RealWorldResult<()> {
+    MIGRATOR
+        .run(pool)
+        .await
+        .map_err(|err| anyhow!("Migration failed {}", err))?;
+    info!("Database migrations are up to date");
+    Ok(())
+}
+
+/// Reverts every applied migration newer than `target`, or only the latest one when
+/// `target` is `None`. Returns the version the database is left at.
+pub async fn revert_migrations(pool: &PgPool, target: Option<i64>) -> RealWorldResult<i64> {
+    let mut applied = applied_versions(pool).await?;
+    applied.sort_unstable();
+
+    let target = match target {
+        Some(target) => target,
+        None => match applied.as_slice() {
+            [] => return Err(anyhow!("No applied migration to revert")),
+            [.., previous, _] => *previous,
+            [_] => 0,
+        },
+    };
+
+    MIGRATOR
+        .undo(pool, target)
+        .await
+        .map_err(|err| anyhow!("Migration revert failed {}", err))?;
+    info!("Database migrations reverted to version {}", target);
+    Ok(target)
+}
+
+pub async fn migration_status(pool: &PgPool) -> RealWorldResult<Vec<MigrationStatus>> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied: HashMap<i64, Vec<u8>> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| (migration.version, migration.checksum.into_owned()))
+        .collect();
+
+    let statuses = MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| {
+            let state = match applied.get(&migration.version) {
+                Some(checksum) if *checksum == *migration.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::ChecksumMismatch,
+                None => MigrationState::Pending,
+            };
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+            }
+        })
+        .collect();
+
+    Ok(statuses)
+}
+
+async fn applied_versions(pool: &PgPool) -> RealWorldResult<Vec<i64>> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied = conn.list_applied_migrations().await?;
+    Ok(applied
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect())
+}
@@ -1,9 +1,14 @@
 use error::AppError;
 
 pub mod app_state;
+pub mod container;
 pub mod db;
-pub mod di_factory;
 pub mod error;
+pub mod logging;
+pub mod metrics;
+pub mod migrate;
+pub mod settings;
+pub mod shutdown;
 pub mod validate;
 
 pub type RealWorldResult<T> = anyhow::Result<T>;
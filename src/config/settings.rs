@@ -0,0 +1,452 @@
+use std::env;
+use std::net::SocketAddr;
+
+use axum::http::{HeaderValue, Method};
+use config_rs::builder::DefaultState;
+use config_rs::{Config, ConfigBuilder, ConfigError, Environment, File, FileFormat};
+use dotenv::dotenv;
+use serde::Deserialize;
+use thiserror::Error;
+use tracing_subscriber::EnvFilter;
+
+use crate::config::error::ErrorFormat;
+use crate::config::logging::LogFormat;
+use crate::middleware::rate_limit::store::Quota;
+
+const DEFAULT_SETTINGS_FILE: &str = "settings.toml";
+const DEFAULT_LOG_FILTER: &str = "info,real_world_axum_sqlx=debug,sqlx::query=debug";
+/// Environment variables read before `Settings` existed, kept as fallbacks for the
+/// keys they used to set.
+const LEGACY_ENV_VARS: &[(&str, &str)] = &[
+    ("DATABASE_URL", "database.url"),
+    ("SECRET_KEY", "jwt.secret_key"),
+    ("RUST_LOG", "logging.filter"),
+    ("ERROR_FORMAT", "server.error_format"),
+    ("LOG_FORMAT", "logging.format"),
+];
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("Failed to load settings: {0}")]
+    Load(#[from] ConfigError),
+
+    #[error("Invalid settings:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Settings {
+    pub server: ServerSettings,
+    pub metrics: MetricsSettings,
+    pub database: DatabaseSettings,
+    pub jwt: JwtSettings,
+    pub cors: CorsSettings,
+    pub rate_limit: RateLimitSettings,
+    pub cache: CacheSettings,
+    pub logging: LoggingSettings,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerSettings {
+    pub listen_addr: SocketAddr,
+    /// After SIGTERM, `/health/ready` reports 503 for this long before the listener
+    /// stops accepting, so load balancers take the instance out of rotation first.
+    pub shutdown_delay_secs: u64,
+    pub shutdown_deadline_secs: u64,
+    pub error_format: ErrorFormat,
+    /// Serve Swagger UI at `/api/docs`; `/api/openapi.json` is always served.
+    pub swagger_ui: bool,
+    /// Larger request bodies are rejected with `413 Payload Too Large`.
+    pub request_body_limit_bytes: usize,
+    /// Requests still running after this are answered with `408 Request Timeout`.
+    pub request_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricsSettings {
+    pub listen_addr: SocketAddr,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatabaseSettings {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_secs: u64,
+    /// `0` keeps idle connections open.
+    pub idle_timeout_secs: u64,
+    /// `0` never retires a connection for its age.
+    pub max_lifetime_secs: u64,
+    pub statement_timeout_ms: Option<u64>,
+    pub application_name: String,
+    pub connect_attempts: u32,
+    pub connect_backoff_initial_ms: u64,
+    pub connect_backoff_max_ms: u64,
+    pub lazy_connect: bool,
+    pub replica_url: Option<String>,
+    pub read_your_writes: bool,
+    /// How long a client's reads stay on the primary after it wrote, `0` pins only
+    /// the request that wrote.
+    pub read_your_writes_window_secs: u64,
+    pub auto_migrate: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtSettings {
+    pub secret_key: String,
+    pub token_ttl_hours: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitSettings {
+    pub enabled: bool,
+    /// Key anonymous clients on the first `X-Forwarded-For` address instead of the peer
+    /// address. Only safe behind a proxy that overwrites the header.
+    pub trust_forwarded_for: bool,
+    /// Registration and login.
+    pub auth: Quota,
+    /// Follow and unfollow.
+    pub follow: Quota,
+    /// Every other API route.
+    pub default: Quota,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CacheSettings {
+    /// Keep users read by id or username in process memory.
+    pub enabled: bool,
+    /// Users kept at most, the least recently used are dropped first.
+    pub capacity: usize,
+    /// Changes made by other instances show up after at most this long.
+    pub ttl_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CorsSettings {
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allow_credentials: bool,
+    pub max_age_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoggingSettings {
+    pub format: LogFormat,
+    pub filter: String,
+}
+
+impl Settings {
+    /// Loads defaults, then the TOML file named by `APP_CONFIG_FILE` (`settings.toml`
+    /// when unset and present), then `APP_` environment variables using `__` between
+    /// sections, e.g. `APP_DATABASE__MAX_CONNECTIONS=20`.
+    ///
+    /// `DATABASE_URL`, `SECRET_KEY` and `RUST_LOG` are still read as defaults for
+    /// their settings so existing deployments keep working.
+    pub fn load() -> Result<Self, SettingsError> {
+        dotenv().ok();
+
+        let file = match env::var("APP_CONFIG_FILE") {
+            Ok(path) => File::with_name(&path).format(FileFormat::Toml),
+            Err(_) => File::with_name(DEFAULT_SETTINGS_FILE)
+                .format(FileFormat::Toml)
+                .required(false),
+        };
+
+        let mut builder = Self::defaults()?;
+        for (var, key) in LEGACY_ENV_VARS {
+            if let Ok(value) = env::var(var) {
+                builder = builder.set_default(*key, value)?;
+            }
+        }
+
+        let settings = builder
+            .add_source(file)
+            .add_source(
+                Environment::with_prefix("APP")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .list_separator(",")
+                    .with_list_parse_key("cors.allowed_origins")
+                    .with_list_parse_key("cors.allowed_methods")
+                    .try_parsing(true),
+            )
+            .build()?
+            .try_deserialize::<Settings>()?;
+
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Defaults overlaid with a TOML document, without reading files or the
+    /// environment. Handy for tests and tooling.
+    pub fn from_toml(toml: &str) -> Result<Self, SettingsError> {
+        let settings = Self::parse_toml(toml)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// `from_toml` without `validate`.
+    pub(crate) fn parse_toml(toml: &str) -> Result<Self, ConfigError> {
+        Self::defaults()?
+            .add_source(File::from_str(toml, FileFormat::Toml))
+            .build()?
+            .try_deserialize::<Settings>()
+    }
+
+    fn defaults() -> Result<ConfigBuilder<DefaultState>, ConfigError> {
+        Config::builder()
+            .set_default("server.listen_addr", "0.0.0.0:8080")?
+            .set_default("server.shutdown_delay_secs", 5)?
+            .set_default("server.shutdown_deadline_secs", 30)?
+            .set_default("server.error_format", "default")?
+            .set_default("server.swagger_ui", false)?
+            .set_default("server.request_body_limit_bytes", 1_048_576)?
+            .set_default("server.request_timeout_secs", 30)?
+            .set_default("metrics.listen_addr", "0.0.0.0:9100")?
+            .set_default("database.url", "")?
+            .set_default("database.max_connections", 10)?
+            .set_default("database.min_connections", 0)?
+            .set_default("database.acquire_timeout_secs", 30)?
+            .set_default("database.idle_timeout_secs", 600)?
+            .set_default("database.max_lifetime_secs", 1800)?
+            .set_default("database.application_name", env!("CARGO_PKG_NAME"))?
+            .set_default("database.connect_attempts", 5)?
+            .set_default("database.connect_backoff_initial_ms", 500)?
+            .set_default("database.connect_backoff_max_ms", 10_000)?
+            .set_default("database.lazy_connect", false)?
+            .set_default("database.read_your_writes", true)?
+            .set_default("database.read_your_writes_window_secs", 5)?
+            .set_default("database.auto_migrate", false)?
+            .set_default("jwt.secret_key", "")?
+            .set_default("jwt.token_ttl_hours", 3)?
+            .set_default("cors.allowed_origins", Vec::<String>::new())?
+            .set_default(
+                "cors.allowed_methods",
+                vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"],
+            )?
+            .set_default("cors.allow_credentials", false)?
+            .set_default("cors.max_age_secs", 3600)?
+            .set_default("rate_limit.enabled", true)?
+            .set_default("rate_limit.trust_forwarded_for", false)?
+            .set_default("rate_limit.auth.capacity", 10)?
+            .set_default("rate_limit.auth.refill_per_minute", 10)?
+            .set_default("rate_limit.follow.capacity", 30)?
+            .set_default("rate_limit.follow.refill_per_minute", 30)?
+            .set_default("rate_limit.default.capacity", 120)?
+            .set_default("rate_limit.default.refill_per_minute", 120)?
+            .set_default("cache.enabled", true)?
+            .set_default("cache.capacity", 10_000)?
+            .set_default("cache.ttl_secs", 60)?
+            .set_default("logging.format", "pretty")?
+            .set_default("logging.filter", DEFAULT_LOG_FILTER)
+    }
+
+    /// Collects every problem at once so a broken deployment is fixed in one pass.
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let mut problems = Vec::new();
+
+        if self.server.request_body_limit_bytes == 0 {
+            problems.push(String::from(
+                "server.request_body_limit_bytes must be at least 1",
+            ));
+        }
+        if self.server.request_timeout_secs == 0 {
+            problems.push(String::from(
+                "server.request_timeout_secs must be at least 1",
+            ));
+        }
+
+        let database_url = &self.database.url;
+        if database_url.is_empty() {
+            problems.push(String::from(
+                "database.url is required (set DATABASE_URL or APP_DATABASE__URL)",
+            ));
+        } else if !database_url.starts_with("postgres://")
+            && !database_url.starts_with("postgresql://")
+        {
+            problems.push(String::from(
+                "database.url must start with postgres:// or postgresql://",
+            ));
+        }
+        if let Some(replica_url) = &self.database.replica_url {
+            if !replica_url.starts_with("postgres://") && !replica_url.starts_with("postgresql://")
+            {
+                problems.push(String::from(
+                    "database.replica_url must start with postgres:// or postgresql://",
+                ));
+            }
+        }
+        if self.database.max_connections == 0 {
+            problems.push(String::from("database.max_connections must be at least 1"));
+        }
+        if self.database.min_connections > self.database.max_connections {
+            problems.push(String::from(
+                "database.min_connections must not exceed database.max_connections",
+            ));
+        }
+        if self.database.connect_attempts == 0 {
+            problems.push(String::from("database.connect_attempts must be at least 1"));
+        }
+        if self.database.connect_backoff_initial_ms > self.database.connect_backoff_max_ms {
+            problems.push(String::from(
+                "database.connect_backoff_initial_ms must not exceed database.connect_backoff_max_ms",
+            ));
+        }
+
+        if self.jwt.secret_key.is_empty() {
+            problems.push(String::from(
+                "jwt.secret_key is required (set SECRET_KEY or APP_JWT__SECRET_KEY)",
+            ));
+        }
+        if self.jwt.token_ttl_hours <= 0 {
+            problems.push(String::from("jwt.token_ttl_hours must be positive"));
+        }
+
+        for origin in &self.cors.allowed_origins {
+            if origin != "*" && HeaderValue::from_str(origin).is_err() {
+                problems.push(format!(
+                    "cors.allowed_origins has an invalid origin {origin:?}"
+                ));
+            }
+        }
+        for method in &self.cors.allowed_methods {
+            if Method::from_bytes(method.as_bytes()).is_err() {
+                problems.push(format!(
+                    "cors.allowed_methods has an invalid method {method:?}"
+                ));
+            }
+        }
+        let wildcard_origin = self.cors.allowed_origins.iter().any(|origin| origin == "*");
+        if wildcard_origin && self.cors.allow_credentials {
+            problems.push(String::from(
+                "cors.allow_credentials cannot be combined with the \"*\" origin",
+            ));
+        }
+
+        for (group, quota) in [
+            ("auth", &self.rate_limit.auth),
+            ("follow", &self.rate_limit.follow),
+            ("default", &self.rate_limit.default),
+        ] {
+            if quota.capacity == 0 || quota.refill_per_minute == 0 {
+                problems.push(format!(
+                    "rate_limit.{group}.capacity and refill_per_minute must be at least 1"
+                ));
+            }
+        }
+
+        if self.cache.enabled && (self.cache.capacity == 0 || self.cache.ttl_secs == 0) {
+            problems.push(String::from(
+                "cache.capacity and cache.ttl_secs must be at least 1",
+            ));
+        }
+
+        if let Err(err) = EnvFilter::try_new(&self.logging.filter) {
+            problems.push(format!("logging.filter is not a valid filter: {err}"));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(SettingsError::Invalid(problems))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::error::ErrorFormat;
+
+    use super::{Settings, SettingsError};
+
+    #[test]
+    fn file_overrides_defaults_test() {
+        let settings = Settings::parse_toml(
+            r#"
+            [server]
+            error_format = "realworld"
+
+            [database]
+            url = "postgres://localhost/realworld"
+            max_connections = 20
+
+            [jwt]
+            secret_key = "secret"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(settings.server.listen_addr.port(), 8080);
+        assert_eq!(settings.server.error_format, ErrorFormat::RealWorld);
+        assert_eq!(settings.database.max_connections, 20);
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_reports_every_problem_test() {
+        let settings = Settings::parse_toml(
+            r#"
+            [database]
+            url = "mysql://localhost/realworld"
+            min_connections = 20
+
+            [cors]
+            allowed_origins = ["*"]
+            allow_credentials = true
+            "#,
+        )
+        .unwrap();
+
+        match settings.validate() {
+            Err(SettingsError::Invalid(problems)) => assert_eq!(problems.len(), 4),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_rejects_bad_cors_and_limits_test() {
+        let settings = Settings::parse_toml(
+            r#"
+            [server]
+            request_body_limit_bytes = 0
+            request_timeout_secs = 0
+
+            [database]
+            url = "postgres://localhost/realworld"
+
+            [jwt]
+            secret_key = "secret"
+
+            [cors]
+            allowed_origins = ["http://localhost:3000", "bad\norigin"]
+            allowed_methods = ["GET", "NOT A METHOD"]
+            "#,
+        )
+        .unwrap();
+
+        match settings.validate() {
+            Err(SettingsError::Invalid(problems)) => assert_eq!(problems.len(), 4),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn disabled_cache_skips_cache_checks_test() {
+        let settings = Settings::parse_toml(
+            r#"
+            [database]
+            url = "postgres://localhost/realworld"
+
+            [jwt]
+            secret_key = "secret"
+
+            [cache]
+            enabled = false
+            capacity = 0
+            "#,
+        )
+        .unwrap();
+
+        assert!(settings.validate().is_ok());
+    }
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use tokio::signal;
+use tokio::sync::watch;
+use tokio::time::sleep;
+use tracing::info;
+
+/// Broadcasts the shutdown request to every listener and the drain deadline.
+#[derive(Clone)]
+pub struct Shutdown {
+    sender: watch::Sender<bool>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Self { sender }
+    }
+
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    /// Resolves once `trigger` has been called.
+    pub async fn triggered(&self) {
+        let mut receiver = self.sender.subscribe();
+        let _ = receiver.wait_for(|triggered| *triggered).await;
+    }
+
+    /// Resolves `deadline` after `trigger`, bounding how long in-flight requests may drain.
+    pub async fn deadline_elapsed(&self, deadline: Duration) {
+        self.triggered().await;
+        sleep(deadline).await;
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Waits for SIGINT (Ctrl+C) or SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("Failed install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    use super::Shutdown;
+
+    #[tokio::test]
+    async fn triggered_after_trigger_test() {
+        let shutdown = Shutdown::new();
+        let waiter = shutdown.clone();
+        let handle = tokio::spawn(async move { waiter.triggered().await });
+
+        shutdown.trigger();
+
+        let result = timeout(Duration::from_secs(1), handle).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn triggered_when_already_triggered_test() {
+        let shutdown = Shutdown::new();
+        shutdown.trigger();
+
+        let result = timeout(Duration::from_secs(1), shutdown.triggered()).await;
+        assert!(result.is_ok());
+    }
+}
@@ -1,15 +1,15 @@
-use std::sync::Arc;
-
 use anyhow::anyhow;
-use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::extract::path::ErrorKind;
+use axum::extract::rejection::{JsonRejection, PathRejection};
+use axum::extract::{FromRef, FromRequest, FromRequestParts, Path, Request};
 use axum::http::request::Parts;
+use axum::http::StatusCode;
 use axum::{async_trait, Json};
-use log::error;
 use serde::de::DeserializeOwned;
+use tracing::error;
 use validator::Validate;
 
-use crate::auth::jwt_decoder::JwtDecoder;
-use crate::config::app_state::AppState;
+use crate::auth::jwt_decoder::ArcJwtDecoder;
 use crate::config::error::AppError;
 
 const AUTHORIZATION: &str = "Authorization";
@@ -26,37 +26,188 @@ where
     type Rejection = AppError;
 
     async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
-        let result = Json::<T>::from_request(req, state).await;
-        let Json(value) = match result {
-            Ok(value) => value,
-            Err(err) => {
-                let msg = err.body_text();
-                error!("Json parsing error {}", msg);
-                if msg.contains("missing field") {
-                    if let Some(field_name) = msg.split("missing field ").nth(1) {
-                        let field_name = field_name
-                            .split_whitespace()
-                            .next()
-                            .unwrap_or("")
-                            .trim_matches('`')
-                            .to_string();
-                        return Err(AppError::MissingFieldError(field_name));
-                    }
-                };
-                error!("{}", err);
-                return Err(AppError::InternalServerError);
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(json_rejection_to_error)?;
+
+        validate(&value)?;
+
+        Ok(ValidationExtractor(value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidationQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidationQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+
+        let value: T = serde_path_to_error::deserialize(deserializer).map_err(|err| {
+            error!("Query string parsing error {}", err);
+            let message = err.inner().to_string();
+            let path = match missing_field_name(&message) {
+                Some(field_name) => join_path(&err.path().to_string(), field_name),
+                None => err.path().to_string(),
+            };
+            AppError::InvalidParameterError { path, message }
+        })?;
+
+        validate(&value)?;
+
+        Ok(ValidationQuery(value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidationPath<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidationPath<T>
+where
+    T: DeserializeOwned + Validate + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(value) = Path::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(path_rejection_to_error)?;
+
+        validate(&value)?;
+
+        Ok(ValidationPath(value))
+    }
+}
+
+fn validate<T: Validate>(value: &T) -> Result<(), AppError> {
+    value.validate().map_err(|errors| {
+        error!("Validation Error {}", errors);
+        AppError::ValidateError(errors)
+    })
+}
+
+fn json_rejection_to_error(rejection: JsonRejection) -> AppError {
+    error!("Json parsing error {}", rejection.body_text());
+
+    match rejection {
+        JsonRejection::JsonDataError(err) => match serde_error(&err) {
+            Some(err) => {
+                let inner = err.inner();
+                let (line, column) = (inner.line(), inner.column());
+                let message = serde_message(inner);
+                let path = err.path().to_string();
+
+                match missing_field_name(&message) {
+                    Some(field_name) => AppError::MissingFieldError {
+                        path: join_path(&path, field_name),
+                        line,
+                        column,
+                    },
+                    None => AppError::InvalidFieldError {
+                        path,
+                        message,
+                        line,
+                        column,
+                    },
+                }
             }
-        };
+            None => AppError::InvalidBodyError {
+                message: err.body_text(),
+            },
+        },
+        JsonRejection::JsonSyntaxError(err) => match serde_error(&err) {
+            Some(err) => AppError::MalformedJsonError {
+                message: serde_message(err.inner()),
+                line: err.inner().line(),
+                column: err.inner().column(),
+            },
+            None => AppError::MalformedBodyError {
+                message: err.body_text(),
+            },
+        },
+        JsonRejection::MissingJsonContentType(_) => AppError::UnsupportedMediaType,
+        JsonRejection::BytesRejection(err) => {
+            if err.status() == StatusCode::PAYLOAD_TOO_LARGE {
+                AppError::PayloadTooLarge
+            } else {
+                AppError::AnyHow(anyhow!(err.body_text()))
+            }
+        }
+        _ => AppError::InternalServerError,
+    }
+}
+
+fn path_rejection_to_error(rejection: PathRejection) -> AppError {
+    error!("Path parsing error {}", rejection.body_text());
 
-        if let Err(errors) = value.validate() {
-            error!("Validation Error {}", errors);
-            return Err(AppError::ValidateError(errors));
+    match rejection {
+        PathRejection::FailedToDeserializePathParams(err) => {
+            let message = err.body_text();
+            let path = match err.into_kind() {
+                ErrorKind::ParseErrorAtKey { key, .. }
+                | ErrorKind::InvalidUtf8InPathParam { key } => key,
+                ErrorKind::ParseErrorAtIndex { index, .. } => index.to_string(),
+                _ => String::from("."),
+            };
+            AppError::InvalidParameterError { path, message }
         }
+        _ => AppError::InternalServerError,
+    }
+}
 
-        Ok(ValidationExtractor(value))
+/// Digs the `serde_path_to_error` error out of axum's json rejection.
+fn serde_error<'a>(
+    rejection: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a serde_path_to_error::Error<serde_json::Error>> {
+    rejection
+        .source()
+        .and_then(|err| err.source())
+        .and_then(|err| err.downcast_ref())
+}
+
+/// `serde_json` appends the position to its message, which we report separately.
+fn serde_message(err: &serde_json::Error) -> String {
+    let message = err.to_string();
+    let position = format!(" at line {} column {}", err.line(), err.column());
+    match message.strip_suffix(&position) {
+        Some(message) => message.to_string(),
+        None => message,
+    }
+}
+
+fn missing_field_name(message: &str) -> Option<&str> {
+    message
+        .strip_prefix("missing field `")
+        .and_then(|field_name| field_name.split('`').next())
+}
+
+fn join_path(parent: &str, field_name: &str) -> String {
+    if parent == "." {
+        field_name.to_string()
+    } else {
+        format!("{parent}.{field_name}")
     }
 }
 
+/// Strips the `Token` scheme used by the RealWorld spec, or `Bearer`.
+pub(crate) fn token_of(header_value: &str) -> &str {
+    header_value
+        .strip_prefix("Token ")
+        .or_else(|| header_value.strip_prefix("Bearer "))
+        .unwrap_or(header_value)
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct JwtValidationExtractor(pub i64);
 
@@ -64,30 +215,21 @@ pub struct JwtValidationExtractor(pub i64);
 impl<S> FromRequestParts<S> for JwtValidationExtractor
 where
     S: Send + Sync,
+    ArcJwtDecoder: FromRef<S>,
 {
     type Rejection = AppError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let token = if let Some(header) = parts.headers.get(AUTHORIZATION) {
-            header
+            let header = header
                 .to_str()
-                .map_err(|_| anyhow!("Invalid header value"))?
-                .replace("Bearer ", "")
+                .map_err(|_| anyhow!("Invalid header value"))?;
+            token_of(header).to_owned()
         } else {
             return Err(AppError::Unauthorized);
         };
 
-        let app_state = parts
-            .extensions
-            .get::<Arc<AppState>>()
-            .ok_or_else(|| {
-                return AppError::AnyHow(anyhow!("Can't get _state"));
-            })
-            .map_err(|err| {
-                return err;
-            })?;
-
-        let jwt_decoder = JwtDecoder::new(app_state.secret_key.clone());
+        let jwt_decoder = ArcJwtDecoder::from_ref(state);
         let payload = jwt_decoder.decode_token(&token)?;
 
         Ok(JwtValidationExtractor(payload.id()))
@@ -101,13 +243,14 @@ pub struct OptionalAuthenticateExtractor(pub Option<i64>);
 impl<S> FromRequestParts<S> for OptionalAuthenticateExtractor
 where
     S: Send + Sync,
+    ArcJwtDecoder: FromRef<S>,
 {
     type Rejection = AppError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let token = if let Some(header) = parts.headers.get(AUTHORIZATION) {
             match header.to_str() {
-                Ok(header_value) => header_value.replace("Bearer ", ""),
+                Ok(header_value) => token_of(header_value).to_owned(),
                 Err(_) => {
                     return Ok(OptionalAuthenticateExtractor(None));
                 }
@@ -116,19 +259,90 @@ where
             return Ok(OptionalAuthenticateExtractor(None));
         };
 
-        let app_state = parts
-            .extensions
-            .get::<Arc<AppState>>()
-            .ok_or_else(|| {
-                return AppError::AnyHow(anyhow!("Can't get _state"));
-            })
-            .map_err(|err| {
-                return err;
-            })?;
-
-        let jwt_decoder = JwtDecoder::new(app_state.secret_key.clone());
+        let jwt_decoder = ArcJwtDecoder::from_ref(state);
         let payload = jwt_decoder.decode_token(&token)?;
 
         Ok(OptionalAuthenticateExtractor(Some(payload.id())))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::extract::{FromRequest, Request};
+    use serde::Deserialize;
+    use validator::Validate;
+    use validator_derive::Validate;
+
+    use crate::config::error::AppError;
+
+    use super::ValidationExtractor;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Inner {
+        #[allow(dead_code)]
+        email: String,
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Outer {
+        #[validate(nested)]
+        #[allow(dead_code)]
+        user: Inner,
+    }
+
+    async fn extract(content_type: &str, body: &'static str) -> Result<Outer, AppError> {
+        let request = Request::builder()
+            .method("POST")
+            .header("Content-Type", content_type)
+            .body(Body::from(body))
+            .unwrap();
+
+        ValidationExtractor::<Outer>::from_request(request, &())
+            .await
+            .map(|ValidationExtractor(value)| value)
+    }
+
+    #[tokio::test]
+    async fn missing_nested_field_test() {
+        let result = extract("application/json", r#"{"user": {}}"#).await;
+
+        match result {
+            Err(AppError::MissingFieldError { path, line, column }) => {
+                assert_eq!(path, "user.email");
+                assert_eq!((line, column), (1, 11));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn type_mismatch_test() {
+        let result = extract("application/json", r#"{"user": {"email": 1}}"#).await;
+
+        match result {
+            Err(AppError::InvalidFieldError { path, message, .. }) => {
+                assert_eq!(path, "user.email");
+                assert_eq!(message, "invalid type: integer `1`, expected a string");
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn syntax_error_test() {
+        let result = extract("application/json", r#"{"user": "#).await;
+
+        assert!(matches!(
+            result,
+            Err(AppError::MalformedJsonError { line: 1, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn wrong_content_type_test() {
+        let result = extract("text/plain", r#"{"user": {"email": "a"}}"#).await;
+
+        assert!(matches!(result, Err(AppError::UnsupportedMediaType)));
+    }
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use tokio::time::timeout;
+use tracing::error;
+use utoipa::ToSchema;
+
+use crate::config::{
+    app_state::ArcAppState,
+    db::{DbPool, PgPool},
+};
+
+const READY_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, ToSchema)]
+pub struct LivenessResponse {
+    status: &'static str,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReadinessResponse {
+    status: &'static str,
+    database: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replica: Option<&'static str>,
+    migration_version: Option<i64>,
+    build: BuildInfo,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BuildInfo {
+    name: &'static str,
+    version: &'static str,
+    git_sha: Option<&'static str>,
+}
+
+impl BuildInfo {
+    fn current() -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: option_env!("BUILD_GIT_SHA"),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses((status = 200, description = "The process is up", body = LivenessResponse))
+)]
+pub async fn liveness() -> Json<LivenessResponse> {
+    Json(LivenessResponse { status: "ok" })
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Ready to serve traffic", body = ReadinessResponse),
+        (status = 503, description = "Draining or a database is unreachable", body = ReadinessResponse),
+    )
+)]
+pub async fn readiness(
+    State(app_state): State<ArcAppState>,
+    State(db_pool): State<DbPool>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let database = check_database(db_pool.primary(), "primary").await;
+    let replica = match db_pool.replica() {
+        Some(replica) => Some(check_database(replica, "replica").await),
+        None => None,
+    };
+
+    let migration_version = if database == "ok" {
+        find_migration_version(db_pool.primary()).await
+    } else {
+        None
+    };
+
+    let (status_code, status) = if app_state.is_shutting_down() {
+        (StatusCode::SERVICE_UNAVAILABLE, "shutting_down")
+    } else if database != "ok" || replica.is_some_and(|replica| replica != "ok") {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    } else {
+        (StatusCode::OK, "ok")
+    };
+
+    let response = ReadinessResponse {
+        status,
+        database,
+        replica,
+        migration_version,
+        build: BuildInfo::current(),
+    };
+    (status_code, Json(response))
+}
+
+async fn check_database(pool: &PgPool, role: &str) -> &'static str {
+    match timeout(READY_CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(pool)).await {
+        Ok(Ok(_)) => "ok",
+        Ok(Err(err)) => {
+            error!("Readiness {} database check failed {}", role, err);
+            "unreachable"
+        }
+        Err(_) => {
+            error!("Readiness {} database check timed out", role);
+            "timeout"
+        }
+    }
+}
+
+/// Latest applied migration, or `None` when migrations have never run.
+async fn find_migration_version(db_pool: &PgPool) -> Option<i64> {
+    let query = sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success");
+
+    match timeout(READY_CHECK_TIMEOUT, query.fetch_one(db_pool)).await {
+        Ok(Ok(version)) => version,
+        _ => None,
+    }
+}
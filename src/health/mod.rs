@@ -0,0 +1,12 @@
+use axum::{routing::get, Router};
+
+use crate::config::container::AppContainer;
+use handler::{liveness, readiness};
+
+pub mod handler;
+
+pub fn health_route() -> Router<AppContainer> {
+    Router::new()
+        .route("/health/live", get(liveness))
+        .route("/health/ready", get(readiness))
+}
@@ -1,48 +1,144 @@
-use std::env;
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::time::Duration;
 
-use axum::{Extension, Router};
-use config::db::{init_db, DbPool};
-use config::di_factory::{create_profile_service, create_user_service};
-use dotenv::dotenv;
+use anyhow::Context;
+use axum::middleware::{from_fn, from_fn_with_state, map_response_with_state};
+use axum::Router;
+use config::db::init_db;
+use health::health_route;
+use openapi::openapi_route;
 use profile::profile_route;
 use tokio::net::TcpListener;
+use tower_http::compression::CompressionLayer;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+use tracing::{error, info, warn};
 use user::user_route;
 
-use crate::config::app_state::{init_app_state, ArcAppState};
-use crate::config::error::error_handler;
+use crate::config::container::AppContainer;
+use crate::config::error::render_error_format;
+use crate::config::metrics::{init_metrics, metrics_route};
+use crate::config::migrate::run_migrations;
+use crate::config::settings::Settings;
+use crate::config::shutdown::{shutdown_signal, Shutdown};
+use crate::config::RealWorldResult;
+use crate::middleware::cors::cors_layer;
+use crate::middleware::limits::render_limit_rejections;
+use crate::middleware::metrics::track_metrics;
+use crate::middleware::read_your_writes::pin_reads_after_write;
+use crate::middleware::request_id::propagate_request_id;
+use crate::middleware::security_headers::set_security_headers;
 
-pub async fn start_application() -> () {
-    dotenv().ok();
-    let database_url = env::var("DATABASE_URL").expect("Failed get DB URL");
-    let db_pool = init_db(database_url.as_ref()).await;
+pub async fn start_application(settings: Settings) -> RealWorldResult<()> {
+    let db_pool = init_db(&settings.database).await?;
+    if settings.database.auto_migrate {
+        run_migrations(db_pool.primary()).await?;
+    }
 
-    let app_state = init_app_state().await;
-    let app_state = Arc::new(app_state);
+    let shutdown_delay = Duration::from_secs(settings.server.shutdown_delay_secs);
+    let shutdown_deadline = Duration::from_secs(settings.server.shutdown_deadline_secs);
+    let shutdown = Shutdown::new();
 
-    log4rs::init_file("log4rs.yaml", Default::default()).unwrap();
+    let metrics_handle = init_metrics();
+    let metrics_listener = TcpListener::bind(settings.metrics.listen_addr)
+        .await
+        .with_context(|| format!("Failed bind metrics on {}", settings.metrics.listen_addr))?;
+    let metrics_route = metrics_route(metrics_handle, db_pool.clone());
+    let metrics_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        let result = axum::serve(metrics_listener, metrics_route.into_make_service())
+            .with_graceful_shutdown(async move { metrics_shutdown.triggered().await })
+            .await;
+        if let Err(err) = result {
+            error!("Metrics server error {}", err);
+        }
+    });
+
+    let listener = TcpListener::bind(settings.server.listen_addr)
+        .await
+        .with_context(|| format!("Failed bind server on {}", settings.server.listen_addr))?;
+    info!("Listening on {}", settings.server.listen_addr);
 
-    let listener = TcpListener::bind("0.0.0.0:8080").await.unwrap();
+    let container = AppContainer::builder(settings, db_pool.clone()).build();
+    let app_state = container.app_state.clone();
+    let route = create_route(container);
 
-    let route = create_route(db_pool, app_state).await;
+    let signal_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!(
+            "Reporting not ready for {:?} before shutting down",
+            shutdown_delay
+        );
+        app_state.begin_shutdown();
+        tokio::time::sleep(shutdown_delay).await;
+        info!("Stop accepting connections, draining in-flight requests");
+        signal_shutdown.trigger();
+    });
 
-    axum::serve(listener, route.into_make_service())
-        .await
-        .unwrap();
+    let server = axum::serve(
+        listener,
+        route.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown({
+        let shutdown = shutdown.clone();
+        async move { shutdown.triggered().await }
+    });
+
+    tokio::select! {
+        result = server => {
+            if let Err(err) = result {
+                error!("Server error {}", err);
+            }
+        }
+        _ = shutdown.deadline_elapsed(shutdown_deadline) => {
+            warn!("Drain deadline of {:?} elapsed, dropping in-flight requests", shutdown_deadline);
+        }
+    }
+
+    db_pool.close().await;
+    info!("Shutdown complete");
+    Ok(())
 }
 
-pub async fn create_route(db_pool: DbPool, app_state: ArcAppState) -> Router {
-    let user_service = create_user_service(db_pool.clone(), app_state.clone());
-    let profile_service = create_profile_service(db_pool.clone(), user_service.clone());
-    Router::new()
-        .nest("/api", user_route())
-        .nest("/api", profile_route())
-        .layer(Extension(error_handler))
-        .layer(Extension(app_state.clone()))
-        .layer(Extension(user_service))
-        .layer(Extension(profile_service))
+pub fn create_route(container: AppContainer) -> Router {
+    let settings = &container.app_state.settings;
+    let error_format = settings.server.error_format;
+    let swagger_ui = settings.server.swagger_ui;
+    let body_limit = settings.server.request_body_limit_bytes;
+    let request_timeout = Duration::from_secs(settings.server.request_timeout_secs);
+    let cors = cors_layer(&settings.cors);
+
+    let router = Router::new()
+        .nest("/api", user_route(&container.rate_limiter))
+        .nest("/api", profile_route(&container.rate_limiter))
+        .route_layer(from_fn_with_state(container.clone(), pin_reads_after_write))
+        .merge(health_route())
+        .merge(openapi_route(swagger_ui))
+        // Route layers, so the metrics see the matched path and the 408 and 413
+        // answers of the limits inside them.
+        .route_layer(RequestBodyLimitLayer::new(body_limit))
+        .route_layer(TimeoutLayer::new(request_timeout))
+        .route_layer(from_fn(track_metrics))
+        .layer(from_fn(render_limit_rejections))
+        .layer(map_response_with_state(error_format, render_error_format))
+        .layer(from_fn(set_security_headers))
+        .layer(CompressionLayer::new());
+    let router = match cors {
+        Some(cors) => router.layer(cors),
+        None => router,
+    };
+    router
+        .layer(from_fn(propagate_request_id))
+        .with_state(container)
 }
 pub mod auth;
+pub mod cli;
 pub mod config;
+pub mod health;
+pub mod middleware;
+pub mod openapi;
 pub mod profile;
+pub mod seed;
+pub mod unit_of_work;
 pub mod user;
@@ -1,6 +1,15 @@
-use real_world_axum_sqlx::start_application;
+use std::process::ExitCode;
+
+use clap::Parser;
+use real_world_axum_sqlx::cli::{run, Cli};
 
 #[tokio::main]
-async fn main() {
-    start_application().await
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err:#}");
+            ExitCode::FAILURE
+        }
+    }
 }
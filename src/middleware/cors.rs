@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::config::settings::CorsSettings;
+use crate::middleware::rate_limit::{LIMIT_HEADER, REMAINING_HEADER, RESET_HEADER};
+use crate::middleware::request_id::REQUEST_ID_HEADER;
+
+/// CORS for the configured origins, or `None` when no origin is allowed so that
+/// browsers keep the API same-origin only. Expects settings that passed validation.
+pub fn cors_layer(settings: &CorsSettings) -> Option<CorsLayer> {
+    if settings.allowed_origins.is_empty() {
+        return None;
+    }
+
+    let allow_origin = if settings.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            settings
+                .allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+    let allow_methods: Vec<Method> = settings
+        .allowed_methods
+        .iter()
+        .filter_map(|method| Method::from_bytes(method.as_bytes()).ok())
+        .collect();
+
+    let layer = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(allow_methods)
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::IF_MATCH,
+            header::IF_NONE_MATCH,
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .expose_headers([
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            HeaderName::from_static(LIMIT_HEADER),
+            HeaderName::from_static(REMAINING_HEADER),
+            HeaderName::from_static(RESET_HEADER),
+            header::RETRY_AFTER,
+            header::ETAG,
+        ])
+        .allow_credentials(settings.allow_credentials)
+        .max_age(Duration::from_secs(settings.max_age_secs));
+    Some(layer)
+}
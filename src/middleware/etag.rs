@@ -0,0 +1,130 @@
+use axum::body::{to_bytes, Body};
+use axum::extract::Request;
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+
+use crate::config::error::AppError;
+
+/// Hex digits of the body digest kept in the tag.
+const ETAG_LENGTH: usize = 32;
+
+/// Tags successful `GET` responses with a weak `ETag` computed from the body, and answers
+/// `304 Not Modified` when the client already holds that version.
+///
+/// The tag is weak because compression may re-encode the body on the way out.
+pub async fn conditional_get(request: Request, next: Next) -> Response {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return next.run(request).await;
+    }
+
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK || response.headers().contains_key(header::ETAG) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(err) => return AppError::AnyHow(err.into()).into_response(),
+    };
+    let etag = weak_etag(&bytes);
+
+    if if_none_match.is_some_and(|value| matches_any(&value, &etag)) {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ETAG, etag);
+        for name in [header::CACHE_CONTROL, header::VARY] {
+            for value in parts.headers.get_all(&name) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+        return (StatusCode::NOT_MODIFIED, headers).into_response();
+    }
+
+    parts.headers.insert(header::ETAG, etag);
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+fn weak_etag(body: &[u8]) -> HeaderValue {
+    let digest = format!("{:x}", Sha256::digest(body));
+    HeaderValue::try_from(format!("W/\"{}\"", &digest[..ETAG_LENGTH])).unwrap()
+}
+
+/// `If-None-Match` uses the weak comparison, so `W/` prefixes are ignored on both sides.
+fn matches_any(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    let Ok(candidates) = if_none_match.to_str() else {
+        return false;
+    };
+    let etag = opaque_tag(etag.to_str().unwrap_or_default());
+    candidates
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || opaque_tag(candidate) == etag)
+}
+
+fn opaque_tag(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{header, HeaderValue, Request, StatusCode};
+    use axum::routing::get;
+    use axum::{middleware, Router};
+    use tower::ServiceExt;
+
+    use super::{conditional_get, matches_any};
+
+    async fn send(
+        router: &Router,
+        if_none_match: Option<&str>,
+    ) -> (StatusCode, Option<HeaderValue>) {
+        let mut request = Request::get("/");
+        if let Some(tag) = if_none_match {
+            request = request.header(header::IF_NONE_MATCH, tag);
+        }
+        let response = router
+            .clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        (
+            response.status(),
+            response.headers().get(header::ETAG).cloned(),
+        )
+    }
+
+    #[tokio::test]
+    async fn matching_tag_is_not_modified_test() {
+        let router = Router::new()
+            .route("/", get(|| async { "profile" }))
+            .layer(middleware::from_fn(conditional_get));
+
+        let (status, etag) = send(&router, None).await;
+        let etag = etag.unwrap();
+        let (cached_status, cached_etag) = send(&router, Some(etag.to_str().unwrap())).await;
+        let (stale_status, _) = send(&router, Some("W/\"stale\"")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(etag.to_str().unwrap().starts_with("W/\""));
+        assert_eq!(cached_status, StatusCode::NOT_MODIFIED);
+        assert_eq!(cached_etag, Some(etag));
+        assert_eq!(stale_status, StatusCode::OK);
+    }
+
+    #[test]
+    fn weak_comparison_ignores_prefix_test() {
+        let etag = HeaderValue::from_static("W/\"abc\"");
+
+        assert!(matches_any(&HeaderValue::from_static("\"abc\""), &etag));
+        assert!(matches_any(
+            &HeaderValue::from_static("\"x\", W/\"abc\""),
+            &etag
+        ));
+        assert!(matches_any(&HeaderValue::from_static("*"), &etag));
+        assert!(!matches_any(&HeaderValue::from_static("\"abcd\""), &etag));
+    }
+}
@@ -0,0 +1,80 @@
+use axum::extract::Request;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::config::error::{is_app_error_response, AppError};
+
+/// The body limit and timeout layers answer with empty `413` and `408` responses;
+/// this turns them into `AppError` bodies like every other error.
+pub async fn render_limit_rejections(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if is_app_error_response(&response) {
+        return response;
+    }
+
+    match response.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge.into_response(),
+        StatusCode::REQUEST_TIMEOUT => AppError::RequestTimeout.into_response(),
+        _ => response,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use axum::routing::{get, post};
+    use axum::{middleware, Router};
+    use serde_json::Value;
+    use tower::ServiceExt;
+    use tower_http::limit::RequestBodyLimitLayer;
+    use tower_http::timeout::TimeoutLayer;
+
+    use super::render_limit_rejections;
+
+    #[tokio::test]
+    async fn timeout_becomes_app_error_test() {
+        let router = Router::new()
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "done"
+                }),
+            )
+            .layer(TimeoutLayer::new(Duration::from_millis(50)))
+            .layer(middleware::from_fn(render_limit_rejections));
+        let request = Request::get("/slow").body(Body::empty()).unwrap();
+
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(status, StatusCode::REQUEST_TIMEOUT);
+        assert_eq!(body["code"], 40800);
+    }
+
+    #[tokio::test]
+    async fn oversized_body_becomes_app_error_test() {
+        let router = Router::new()
+            .route("/echo", post(|body: String| async move { body }))
+            .layer(RequestBodyLimitLayer::new(8))
+            .layer(middleware::from_fn(render_limit_rejections));
+        let request = Request::post("/echo")
+            .header("content-length", "16")
+            .body(Body::from("0123456789abcdef"))
+            .unwrap();
+
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(body["code"], 41300);
+    }
+}
@@ -0,0 +1,32 @@
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use metrics::{counter, histogram};
+
+use crate::config::metrics::{HTTP_REQUESTS_TOTAL, HTTP_REQUEST_DURATION_SECONDS};
+
+/// Records request count and latency labelled by the matched route template
+/// (`/api/profiles/:username`), so path parameters do not blow up cardinality.
+/// Must be added with `route_layer` for `MatchedPath` to be available.
+pub async fn track_metrics(request: Request, next: Next) -> Response {
+    let path = match request.extensions().get::<MatchedPath>() {
+        Some(matched_path) => matched_path.as_str().to_owned(),
+        None => String::from("unmatched"),
+    };
+    let method = request.method().to_string();
+    let started_at = Instant::now();
+
+    let response = next.run(request).await;
+
+    let labels = [
+        ("method", method),
+        ("path", path),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    counter!(HTTP_REQUESTS_TOTAL, &labels).increment(1);
+    histogram!(HTTP_REQUEST_DURATION_SECONDS, &labels).record(started_at.elapsed());
+
+    response
+}
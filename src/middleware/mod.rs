@@ -0,0 +1,8 @@
+pub mod cors;
+pub mod etag;
+pub mod limits;
+pub mod metrics;
+pub mod rate_limit;
+pub mod read_your_writes;
+pub mod request_id;
+pub mod security_headers;
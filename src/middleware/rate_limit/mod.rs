@@ -0,0 +1,297 @@
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use tower::{Layer, Service};
+use tracing::warn;
+
+use crate::auth::clock::DynClock;
+use crate::auth::jwt_decoder::ArcJwtDecoder;
+use crate::config::error::AppError;
+use crate::config::settings::RateLimitSettings;
+use crate::config::validate::token_of;
+
+use store::{DynRateLimitStore, Quota, RateLimitDecision};
+
+pub mod store;
+
+pub(crate) const LIMIT_HEADER: &str = "x-ratelimit-limit";
+pub(crate) const REMAINING_HEADER: &str = "x-ratelimit-remaining";
+pub(crate) const RESET_HEADER: &str = "x-ratelimit-reset";
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+pub type ArcRateLimiter = Arc<RateLimiter>;
+
+/// Routes sharing one set of buckets and one quota from `RateLimitSettings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitGroup {
+    /// Registration and login, where guessing passwords is the concern.
+    Auth,
+    /// Follow and unfollow, which write on every call.
+    Follow,
+    Default,
+}
+
+impl RateLimitGroup {
+    fn name(&self) -> &'static str {
+        match self {
+            RateLimitGroup::Auth => "auth",
+            RateLimitGroup::Follow => "follow",
+            RateLimitGroup::Default => "default",
+        }
+    }
+}
+
+/// Decides whether a request may proceed. Clients are told apart by the user id in
+/// their token, or by their IP address when they send none.
+pub struct RateLimiter {
+    settings: RateLimitSettings,
+    store: DynRateLimitStore,
+    jwt_decoder: ArcJwtDecoder,
+    clock: DynClock,
+}
+
+impl RateLimiter {
+    pub fn new(
+        settings: RateLimitSettings,
+        store: DynRateLimitStore,
+        jwt_decoder: ArcJwtDecoder,
+        clock: DynClock,
+    ) -> Self {
+        Self {
+            settings,
+            store,
+            jwt_decoder,
+            clock,
+        }
+    }
+
+    fn quota(&self, group: RateLimitGroup) -> Quota {
+        match group {
+            RateLimitGroup::Auth => self.settings.auth,
+            RateLimitGroup::Follow => self.settings.follow,
+            RateLimitGroup::Default => self.settings.default,
+        }
+    }
+
+    fn client_key(&self, request: &Request) -> String {
+        let user_id = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                self.jwt_decoder
+                    .decode_token(&token_of(value).to_owned())
+                    .ok()
+            })
+            .map(|payload| payload.id());
+        if let Some(user_id) = user_id {
+            return format!("user:{user_id}");
+        }
+
+        let forwarded_for = self
+            .settings
+            .trust_forwarded_for
+            .then(|| forwarded_for(request.headers()))
+            .flatten();
+        let connected = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string());
+        match forwarded_for.or(connected) {
+            Some(ip) => format!("ip:{ip}"),
+            None => String::from("ip:unknown"),
+        }
+    }
+
+    async fn check(&self, group: RateLimitGroup, client_key: String) -> Option<RateLimitDecision> {
+        let key = format!("{}:{}", group.name(), client_key);
+        match self
+            .store
+            .acquire(&key, self.quota(group), self.clock.now())
+            .await
+        {
+            Ok(decision) => Some(decision),
+            Err(err) => {
+                // Failing open: a broken store must not take the API down with it.
+                warn!("Rate limit store failed, letting {} through {}", key, err);
+                None
+            }
+        }
+    }
+
+    pub fn layer(self: &Arc<Self>, group: RateLimitGroup) -> RateLimitLayer {
+        RateLimitLayer {
+            limiter: self.clone(),
+            group,
+        }
+    }
+}
+
+/// First address in `X-Forwarded-For`, the client as seen by the outermost proxy.
+fn forwarded_for(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(FORWARDED_FOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|ip| ip.trim().to_owned())
+        .filter(|ip| !ip.is_empty())
+}
+
+fn set_rate_limit_headers(response: &mut Response, decision: &RateLimitDecision) {
+    let headers = response.headers_mut();
+    headers.insert(LIMIT_HEADER, HeaderValue::from(decision.limit));
+    headers.insert(REMAINING_HEADER, HeaderValue::from(decision.remaining));
+    headers.insert(RESET_HEADER, HeaderValue::from(decision.reset_secs));
+    if !decision.allowed {
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(decision.retry_after_secs),
+        );
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: ArcRateLimiter,
+    group: RateLimitGroup,
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            limiter: self.limiter.clone(),
+            group: self.group,
+        }
+    }
+}
+
+/// Answers with `429 Too Many Requests` once the client's bucket is empty, and adds
+/// `X-RateLimit-*` headers to every response.
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: ArcRateLimiter,
+    group: RateLimitGroup,
+}
+
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // The clone is not ready yet, so call the instance `poll_ready` prepared.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limiter = self.limiter.clone();
+        let group = self.group;
+
+        Box::pin(async move {
+            if !limiter.settings.enabled {
+                return inner.call(request).await;
+            }
+
+            let client_key = limiter.client_key(&request);
+            let Some(decision) = limiter.check(group, client_key).await else {
+                return inner.call(request).await;
+            };
+            let mut response = if decision.allowed {
+                inner.call(request).await?
+            } else {
+                AppError::TooManyRequests.into_response()
+            };
+            set_rate_limit_headers(&mut response, &decision);
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::body::Body;
+    use axum::http::{header, Request, StatusCode};
+    use axum::routing::get;
+    use axum::Router;
+    use chrono::{Duration, TimeZone, Utc};
+    use tower::ServiceExt;
+
+    use crate::auth::clock::MockClock;
+    use crate::auth::jwt_decoder::JwtDecoder;
+    use crate::config::settings::RateLimitSettings;
+
+    use super::store::{InMemoryRateLimitStore, Quota};
+    use super::{RateLimitGroup, RateLimiter};
+
+    const QUOTA: Quota = Quota {
+        capacity: 2,
+        refill_per_minute: 60,
+    };
+
+    async fn send(router: &Router, client: &str) -> (StatusCode, axum::http::HeaderMap) {
+        let request = Request::get("/")
+            .header("x-forwarded-for", client)
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        (response.status(), response.headers().clone())
+    }
+
+    #[tokio::test]
+    async fn rejects_client_over_quota_test() {
+        let clock = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        ));
+        let settings = RateLimitSettings {
+            enabled: true,
+            trust_forwarded_for: true,
+            auth: QUOTA,
+            follow: QUOTA,
+            default: QUOTA,
+        };
+        let limiter = Arc::new(RateLimiter::new(
+            settings,
+            Arc::new(InMemoryRateLimitStore::new()),
+            Arc::new(JwtDecoder::new(String::from("secret"), clock.clone())),
+            clock.clone(),
+        ));
+        let router = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route_layer(limiter.layer(RateLimitGroup::Default));
+
+        let (first, headers) = send(&router, "10.0.0.1").await;
+        send(&router, "10.0.0.1").await;
+        let (limited, limited_headers) = send(&router, "10.0.0.1").await;
+        let (other_client, _) = send(&router, "10.0.0.2").await;
+        clock.advance(Duration::seconds(1));
+        let (after_refill, _) = send(&router, "10.0.0.1").await;
+
+        assert_eq!(first, StatusCode::OK);
+        assert_eq!(headers["x-ratelimit-limit"], "2");
+        assert_eq!(headers["x-ratelimit-remaining"], "1");
+        assert_eq!(limited, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(limited_headers["x-ratelimit-remaining"], "0");
+        assert_eq!(limited_headers[header::RETRY_AFTER], "1");
+        assert_eq!(other_client, StatusCode::OK);
+        assert_eq!(after_refill, StatusCode::OK);
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+
+use crate::config::RealWorldResult;
+
+pub type DynRateLimitStore = Arc<dyn RateLimitStore + Send + Sync>;
+
+/// Idle buckets are dropped once the in-memory store holds this many keys, at most
+/// once per `SWEEP_INTERVAL_SECS` so a store full of busy buckets is not rescanned on
+/// every request.
+const SWEEP_THRESHOLD: usize = 10_000;
+const SWEEP_INTERVAL_SECS: i64 = 10;
+
+/// Token bucket size: `capacity` requests at once, refilled at `refill_per_minute`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct Quota {
+    pub capacity: u32,
+    pub refill_per_minute: u32,
+}
+
+impl Quota {
+    fn refill_per_sec(&self) -> f64 {
+        f64::from(self.refill_per_minute) / 60.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again.
+    pub reset_secs: u64,
+    /// Seconds until the next request is allowed, zero when this one was.
+    pub retry_after_secs: u64,
+}
+
+/// Where the token buckets live. The in-memory store only limits a single instance;
+/// a shared store lets several instances enforce one limit.
+#[async_trait]
+pub trait RateLimitStore {
+    /// Refills the bucket `key` for the time passed since its last use, then tries to
+    /// take one token from it.
+    async fn acquire(
+        &self,
+        key: &str,
+        quota: Quota,
+        now: DateTime<Utc>,
+    ) -> RealWorldResult<RateLimitDecision>;
+}
+
+#[derive(Default)]
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<Buckets>,
+}
+
+#[derive(Default)]
+struct Buckets {
+    by_key: HashMap<String, Bucket>,
+    swept_at: Option<DateTime<Utc>>,
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: DateTime<Utc>,
+    /// Quota of the last request, so sweeps judge the bucket by its own group.
+    quota: Quota,
+}
+
+impl Bucket {
+    fn full(quota: Quota, now: DateTime<Utc>) -> Self {
+        Self {
+            tokens: f64::from(quota.capacity),
+            updated_at: now,
+            quota,
+        }
+    }
+
+    fn refill(&mut self, quota: Quota, now: DateTime<Utc>) {
+        let elapsed = (now - self.updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+        self.tokens =
+            (self.tokens + elapsed * quota.refill_per_sec()).min(f64::from(quota.capacity));
+        self.updated_at = now;
+        self.quota = quota;
+    }
+
+    fn take(&mut self, quota: Quota) -> RateLimitDecision {
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+
+        let rate = quota.refill_per_sec();
+        let retry_after_secs = if allowed {
+            0
+        } else {
+            ((1.0 - self.tokens) / rate).ceil() as u64
+        };
+        RateLimitDecision {
+            allowed,
+            limit: quota.capacity,
+            remaining: self.tokens.floor() as u32,
+            reset_secs: ((f64::from(quota.capacity) - self.tokens) / rate).ceil() as u64,
+            retry_after_secs,
+        }
+    }
+
+    fn is_full(&self, now: DateTime<Utc>) -> bool {
+        let mut bucket = *self;
+        bucket.refill(self.quota, now);
+        bucket.tokens >= f64::from(self.quota.capacity)
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn acquire(
+        &self,
+        key: &str,
+        quota: Quota,
+        now: DateTime<Utc>,
+    ) -> RealWorldResult<RateLimitDecision> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let sweep_due = buckets
+            .swept_at
+            .is_none_or(|swept_at| now - swept_at >= Duration::seconds(SWEEP_INTERVAL_SECS));
+        if buckets.by_key.len() >= SWEEP_THRESHOLD && !buckets.by_key.contains_key(key) && sweep_due
+        {
+            // A full bucket behaves exactly like a missing one.
+            buckets.by_key.retain(|_, bucket| !bucket.is_full(now));
+            buckets.swept_at = Some(now);
+        }
+
+        let bucket = buckets
+            .by_key
+            .entry(key.to_owned())
+            .or_insert_with(|| Bucket::full(quota, now));
+        bucket.refill(quota, now);
+        Ok(bucket.take(quota))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::{InMemoryRateLimitStore, Quota, RateLimitStore, SWEEP_THRESHOLD};
+
+    const QUOTA: Quota = Quota {
+        capacity: 2,
+        refill_per_minute: 6,
+    };
+
+    #[tokio::test]
+    async fn bucket_empties_and_refills_test() {
+        let store = InMemoryRateLimitStore::new();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        let first = store.acquire("key", QUOTA, start).await.unwrap();
+        let second = store.acquire("key", QUOTA, start).await.unwrap();
+        let rejected = store.acquire("key", QUOTA, start).await.unwrap();
+        let other_key = store.acquire("other", QUOTA, start).await.unwrap();
+        let refilled = store
+            .acquire("key", QUOTA, start + Duration::seconds(10))
+            .await
+            .unwrap();
+
+        assert!(first.allowed && first.remaining == 1);
+        assert!(second.allowed && second.remaining == 0);
+        assert!(!rejected.allowed);
+        assert_eq!(rejected.retry_after_secs, 10);
+        assert_eq!(rejected.reset_secs, 20);
+        assert!(other_key.allowed);
+        assert!(refilled.allowed);
+    }
+
+    #[tokio::test]
+    async fn sweep_judges_buckets_by_their_own_quota_test() {
+        let store = InMemoryRateLimitStore::new();
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let generous = Quota {
+            capacity: 100,
+            refill_per_minute: 6000,
+        };
+
+        store.acquire("auth", QUOTA, start).await.unwrap();
+        store.acquire("auth", QUOTA, start).await.unwrap();
+        for index in 1..SWEEP_THRESHOLD {
+            let key = format!("default:{index}");
+            store.acquire(&key, generous, start).await.unwrap();
+        }
+        let later = start + Duration::seconds(1);
+        store.acquire("default:new", generous, later).await.unwrap();
+        let drained = store.acquire("auth", QUOTA, later).await.unwrap();
+
+        assert!(!drained.allowed);
+    }
+}
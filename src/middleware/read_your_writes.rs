@@ -0,0 +1,52 @@
+use axum::extract::{Request, State};
+use axum::http::header::{COOKIE, SET_COOKIE};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::auth::clock::DynClock;
+use crate::config::app_state::ArcAppState;
+use crate::config::db::{track_writes, DbPool};
+
+/// Unix time until which the client's reads go to the primary.
+const READ_PRIMARY_UNTIL_COOKIE: &str = "read_primary_until";
+
+/// Gives every request its own write marker so that, once it writes to the primary,
+/// its later reads skip the replica and see their own changes. A request that wrote
+/// also answers with a cookie that keeps the client's next requests on the primary
+/// for `database.read_your_writes_window_secs`, on whichever instance they land.
+pub async fn pin_reads_after_write(
+    State(app_state): State<ArcAppState>,
+    State(db_pool): State<DbPool>,
+    State(clock): State<DynClock>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let now = clock.now().timestamp();
+    let pinned = read_primary_until(request.headers()).is_some_and(|until| until > now);
+
+    let (mut response, wrote) = track_writes(pinned, next.run(request)).await;
+
+    let settings = &app_state.settings.database;
+    let window_secs = settings.read_your_writes_window_secs;
+    if wrote && settings.read_your_writes && window_secs > 0 && db_pool.replica().is_some() {
+        let cookie = format!(
+            "{READ_PRIMARY_UNTIL_COOKIE}={}; Max-Age={window_secs}; Path=/; HttpOnly; SameSite=Lax",
+            now + window_secs as i64
+        );
+        let cookie = HeaderValue::from_str(&cookie).expect("cookie is plain ascii");
+        response.headers_mut().append(SET_COOKIE, cookie);
+    }
+    response
+}
+
+fn read_primary_until(headers: &HeaderMap) -> Option<i64> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == READ_PRIMARY_UNTIL_COOKIE)
+        .and_then(|(_, value)| value.parse().ok())
+}
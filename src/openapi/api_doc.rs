@@ -0,0 +1,74 @@
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::config::error::{ErrorResponse, RealWorldErrorResponse};
+use crate::health::handler::{BuildInfo, LivenessResponse, ReadinessResponse};
+use crate::profile::api::model::{ProfileBody, ProfileResponse};
+use crate::user::user_handler::{
+    UserBody, UserLoginBody, UserLoginRequest, UserRegisterApiRequest, UserRegisterBody,
+    UserResponse, UserUpdateApiRequest, UserUpdateBody,
+};
+use crate::{health, profile, user};
+
+/// OpenAPI document generated from the handlers and DTOs; `docs/openapi.json` is a
+/// committed copy that the `openapi` integration test keeps in sync.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "RealWorld API",
+        description = "Conduit backend, see https://realworld-docs.netlify.app"
+    ),
+    paths(
+        user::user_handler::register_api,
+        user::user_handler::login_api,
+        user::user_handler::get_info_api,
+        user::user_handler::update_user_api,
+        profile::api::handler::get_profile,
+        profile::api::handler::follow_user_api,
+        profile::api::handler::unfollow_api,
+        health::handler::liveness,
+        health::handler::readiness,
+    ),
+    components(schemas(
+        UserRegisterBody,
+        UserLoginBody,
+        UserUpdateBody,
+        UserBody,
+        UserRegisterApiRequest,
+        UserLoginRequest,
+        UserUpdateApiRequest,
+        UserResponse,
+        ProfileBody,
+        ProfileResponse,
+        LivenessResponse,
+        ReadinessResponse,
+        BuildInfo,
+        ErrorResponse,
+        RealWorldErrorResponse,
+    )),
+    modifiers(&TokenSecurity),
+    tags(
+        (name = "user", description = "Registration, login and the current user"),
+        (name = "profile", description = "Profiles and follows"),
+        (name = "health", description = "Liveness and readiness probes"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the `token` scheme the secured paths refer to.
+struct TokenSecurity;
+
+impl Modify for TokenSecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // Taken from Cargo.toml, which declares no license.
+        openapi.info.license = None;
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "token",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "Authorization",
+                "`Token <jwt>` as in the RealWorld spec; `Bearer <jwt>` is accepted too",
+            ))),
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use axum::http::header;
+use axum::response::{Html, IntoResponse};
+use axum::Json;
+use utoipa::OpenApi;
+
+use super::api_doc::ApiDoc;
+
+const SWAGGER_UI_VERSION: &str = "5.17.14";
+const SWAGGER_UI_CONTENT_SECURITY_POLICY: &str = "default-src 'none'; \
+    script-src 'unsafe-inline' https://unpkg.com; style-src https://unpkg.com; \
+    img-src data: https://unpkg.com; connect-src 'self'";
+
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI page; its assets come from a CDN so nothing is bundled into the binary.
+pub async fn swagger_ui() -> impl IntoResponse {
+    let page = Html(format!(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>RealWorld API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@{version}/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@{version}/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({{ url: "/api/openapi.json", dom_id: "#swagger-ui" }});
+  </script>
+</body>
+</html>
+"##,
+        version = SWAGGER_UI_VERSION
+    ));
+    (
+        [(
+            header::CONTENT_SECURITY_POLICY,
+            SWAGGER_UI_CONTENT_SECURITY_POLICY,
+        )],
+        page,
+    )
+}
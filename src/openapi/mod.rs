@@ -0,0 +1,16 @@
+use axum::{routing::get, Router};
+use handler::{openapi_json, swagger_ui};
+
+use crate::config::container::AppContainer;
+
+pub mod api_doc;
+pub mod handler;
+
+pub fn openapi_route(swagger_ui_enabled: bool) -> Router<AppContainer> {
+    let route = Router::new().route("/api/openapi.json", get(openapi_json));
+    if swagger_ui_enabled {
+        route.route("/api/docs", get(swagger_ui))
+    } else {
+        route
+    }
+}
@@ -1,4 +1,7 @@
-use axum::{extract::Path, Extension, Json};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
 
 use crate::{
     config::{
@@ -10,9 +13,22 @@ use crate::{
 
 use super::model::{ProfileResponse, ProfileResponseDto};
 
+#[utoipa::path(
+    get,
+    path = "/api/profiles/{username}",
+    tag = "profile",
+    params(("username" = String, Path, description = "Username of the profile")),
+    security((), ("token" = [])),
+    responses(
+        (status = 200, description = "Profile, `following` is false without a token", body = ProfileBody,
+            headers(("ETag" = String, description = "Weak tag of the body, send it back in `If-None-Match`"))),
+        (status = 304, description = "Unchanged since the `If-None-Match` tag"),
+        (status = 400, description = "Unknown user or invalid token", body = ErrorResponse),
+    )
+)]
 pub async fn get_profile(
     OptionalAuthenticateExtractor(id): OptionalAuthenticateExtractor,
-    Extension(service): Extension<DynProfileService>,
+    State(service): State<DynProfileService>,
     Path(username): Path<String>,
 ) -> Result<Json<ProfileResponseDto<ProfileResponse>>, AppError> {
     let profile = service.get_profile(id, username).await?;
@@ -24,9 +40,21 @@ pub async fn get_profile(
     Ok(Json(response))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/profiles/{username}/follow",
+    tag = "profile",
+    params(("username" = String, Path, description = "Username to follow")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Followed profile", body = ProfileBody),
+        (status = 400, description = "Unknown user or already followed", body = ErrorResponse),
+        (status = 401, description = "Missing token", body = ErrorResponse),
+    )
+)]
 pub async fn follow_user_api(
     JwtValidationExtractor(follower_id): JwtValidationExtractor,
-    Extension(service): Extension<DynProfileService>,
+    State(service): State<DynProfileService>,
     Path(username): Path<String>,
 ) -> Result<Json<ProfileResponseDto<ProfileResponse>>, AppError> {
     let profile = service.follow_user(follower_id, username).await?;
@@ -38,12 +66,24 @@ pub async fn follow_user_api(
     Ok(Json(response))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/profiles/{username}/follow",
+    tag = "profile",
+    params(("username" = String, Path, description = "Username to unfollow")),
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Unfollowed profile", body = ProfileBody),
+        (status = 400, description = "Unknown user", body = ErrorResponse),
+        (status = 401, description = "Missing token", body = ErrorResponse),
+    )
+)]
 pub async fn unfollow_api(
     JwtValidationExtractor(follower_id): JwtValidationExtractor,
-    Extension(service): Extension<DynProfileService>,
+    State(service): State<DynProfileService>,
     Path(username): Path<String>,
 ) -> Result<Json<ProfileResponseDto<ProfileResponse>>, AppError> {
-    let profile = service.ungfollow(follower_id, username).await?;
+    let profile = service.unfollow(follower_id, username).await?;
 
     let response = ProfileResponse::from(profile);
 
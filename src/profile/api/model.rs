@@ -1,13 +1,15 @@
 use serde::Serialize;
+use utoipa::ToSchema;
 
 use crate::profile::domain::model::Profile;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[aliases(ProfileBody = ProfileResponseDto<ProfileResponse>)]
 pub struct ProfileResponseDto<T: Serialize> {
     pub profile: T,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ProfileResponse {
     username: String,
     bio: Option<String>,
@@ -1,17 +1,38 @@
 use api::handler::{follow_user_api, get_profile, unfollow_api};
 use axum::{
+    http::{header, HeaderValue},
+    middleware::from_fn,
     routing::{delete, get, post},
     Router,
 };
+use tower_http::set_header::SetResponseHeaderLayer;
+
+use crate::config::container::AppContainer;
+use crate::middleware::etag::conditional_get;
+use crate::middleware::rate_limit::{ArcRateLimiter, RateLimitGroup};
 
 pub mod api;
 pub mod domain;
 pub mod repository;
 pub mod service;
 
-pub fn profile_route() -> Router {
+pub fn profile_route(rate_limiter: &ArcRateLimiter) -> Router<AppContainer> {
+    let follow_route = Router::new()
+        .route(
+            "/profiles/:username/follow",
+            post(follow_user_api).delete(unfollow_api),
+        )
+        .route("/profiles/:username/unfollow", delete(unfollow_api))
+        .route_layer(rate_limiter.layer(RateLimitGroup::Follow));
+
     Router::new()
         .route("/profiles/:username", get(get_profile))
-        .route("/profiles/:username/follow", post(follow_user_api))
-        .route("/profiles/:username/unfollow", delete(unfollow_api))
+        // `following` depends on the caller, so caches must not share the body.
+        .route_layer(SetResponseHeaderLayer::appending(
+            header::VARY,
+            HeaderValue::from_static("authorization"),
+        ))
+        .route_layer(from_fn(conditional_get))
+        .route_layer(rate_limiter.layer(RateLimitGroup::Default))
+        .merge(follow_route)
 }
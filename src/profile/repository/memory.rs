@@ -0,0 +1,62 @@
+use std::sync::RwLock;
+
+use axum::async_trait;
+
+use crate::{config::RealWorldResult, profile::domain::repository::ProfileRepository};
+
+/// `ProfileRepository` kept in process memory. Like the `user_follow` table, every
+/// follow adds a row and unfollowing soft deletes all matching rows.
+#[derive(Default)]
+pub struct InMemoryProfileRepository {
+    follows: RwLock<Vec<FollowRow>>,
+}
+
+#[derive(Clone)]
+pub(crate) struct FollowRow {
+    follower_id: i64,
+    following_id: i64,
+    deleted: bool,
+}
+
+impl InMemoryProfileRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy of every row, for `InMemoryUnitOfWork` to roll back to.
+    pub(crate) fn snapshot(&self) -> Vec<FollowRow> {
+        self.follows.read().unwrap().clone()
+    }
+
+    pub(crate) fn restore(&self, follows: Vec<FollowRow>) {
+        *self.follows.write().unwrap() = follows;
+    }
+}
+
+#[async_trait]
+impl ProfileRepository for InMemoryProfileRepository {
+    async fn is_follow(&self, follower_id: i64, following_id: i64) -> RealWorldResult<bool> {
+        let follows = self.follows.read().unwrap();
+        Ok(follows.iter().any(|row| {
+            row.follower_id == follower_id && row.following_id == following_id && !row.deleted
+        }))
+    }
+
+    async fn follow_user(&self, follower_id: i64, following_id: i64) -> RealWorldResult<()> {
+        self.follows.write().unwrap().push(FollowRow {
+            follower_id,
+            following_id,
+            deleted: false,
+        });
+        Ok(())
+    }
+
+    async fn unfollow(&self, follower_id: i64, following_id: i64) -> RealWorldResult<()> {
+        let mut follows = self.follows.write().unwrap();
+        follows
+            .iter_mut()
+            .filter(|row| row.follower_id == follower_id && row.following_id == following_id)
+            .for_each(|row| row.deleted = true);
+        Ok(())
+    }
+}
@@ -1,2 +1,3 @@
+pub mod memory;
 mod model;
 pub mod repository;
@@ -1,23 +1,35 @@
 use anyhow::anyhow;
 use axum::async_trait;
+use tracing::instrument;
 
 use crate::{
-    config::{db::DbPool, RealWorldResult},
+    auth::clock::DynClock,
+    config::{
+        db::{DbPool, DbScope},
+        RealWorldResult,
+    },
     profile::domain::repository::ProfileRepository,
 };
 
 pub struct ConcreteProfileRepository {
-    db_pool: DbPool,
+    db: DbScope,
+    clock: DynClock,
 }
 
 impl ConcreteProfileRepository {
-    pub fn new(pool: DbPool) -> Self {
-        Self { db_pool: pool }
+    pub fn new(pool: DbPool, clock: DynClock) -> Self {
+        Self::in_scope(DbScope::Pool(pool), clock)
+    }
+
+    /// Repository whose statements run in `db`, e.g. a unit of work's transaction.
+    pub fn in_scope(db: DbScope, clock: DynClock) -> Self {
+        Self { db, clock }
     }
 }
 
 #[async_trait]
 impl ProfileRepository for ConcreteProfileRepository {
+    #[instrument(level = "debug", skip(self))]
     async fn is_follow(&self, follower_id: i64, following_id: i64) -> RealWorldResult<bool> {
         let is_follow = sqlx::query!(
             "
@@ -30,22 +42,24 @@ impl ProfileRepository for ConcreteProfileRepository {
             follower_id,
             following_id
         )
-        .fetch_optional(&self.db_pool)
+        .fetch_optional(&mut *self.db.reader().await?)
         .await?;
 
         Ok(is_follow.is_some())
     }
 
+    #[instrument(level = "debug", skip(self))]
     async fn follow_user(&self, follower_id: i64, following_id: i64) -> RealWorldResult<()> {
         let result = sqlx::query!(
             "
-            INSERT INTO user_follow (follower_id, following_id)
-            VALUES ($1, $2)
+            INSERT INTO user_follow (follower_id, following_id, created_at)
+            VALUES ($1, $2, $3)
             ",
             follower_id,
-            following_id
+            following_id,
+            self.clock.now().naive_utc()
         )
-        .execute(&self.db_pool)
+        .execute(&mut *self.db.writer().await?)
         .await;
 
         match result {
@@ -54,17 +68,19 @@ impl ProfileRepository for ConcreteProfileRepository {
         }
     }
 
+    #[instrument(level = "debug", skip(self))]
     async fn unfollow(&self, follower_id: i64, following_id: i64) -> RealWorldResult<()> {
         let result = sqlx::query!(
             "
-            UPDATE user_follow SET deleted = true
+            UPDATE user_follow SET deleted = true, updated_at = $3
             WHERE follower_id = $1
             AND following_id = $2
             ",
             follower_id,
-            following_id
+            following_id,
+            self.clock.now().naive_utc()
         )
-        .execute(&self.db_pool)
+        .execute(&mut *self.db.writer().await?)
         .await;
 
         match result {
@@ -1,29 +1,41 @@
 use anyhow::Ok;
 use axum::async_trait;
-use log::{error, info};
+use metrics::counter;
+use tracing::{error, info, instrument};
 
 use crate::{
-    config::RealWorldResult,
+    config::{
+        metrics::{PROFILE_FOLLOWS_TOTAL, PROFILE_UNFOLLOWS_TOTAL},
+        RealWorldResult,
+    },
     profile::domain::{model::Profile, repository::DynProfileRepository, service::ProfileService},
+    unit_of_work::DynUnitOfWork,
     user::domain::service::DynUserService,
 };
 
 pub struct ConcreteProfileService {
     repository: DynProfileRepository,
     user_service: DynUserService,
+    unit_of_work: DynUnitOfWork,
 }
 
 impl ConcreteProfileService {
-    pub fn new(repository: DynProfileRepository, user_service: DynUserService) -> Self {
+    pub fn new(
+        repository: DynProfileRepository,
+        user_service: DynUserService,
+        unit_of_work: DynUnitOfWork,
+    ) -> Self {
         Self {
             repository,
             user_service,
+            unit_of_work,
         }
     }
 }
 
 #[async_trait]
 impl ProfileService for ConcreteProfileService {
+    #[instrument(skip(self))]
     async fn get_profile(
         &self,
         optional_user: Option<i64>,
@@ -55,18 +67,22 @@ impl ProfileService for ConcreteProfileService {
         }
     }
 
+    #[instrument(skip(self))]
     async fn follow_user(
         &self,
         follower_id: i64,
         following_username: String,
     ) -> RealWorldResult<Profile> {
-        let following_user = self
-            .user_service
-            .get_info_by_user_name(following_username)
+        let transaction = self.unit_of_work.begin().await?;
+        let following_user = transaction
+            .users()
+            .find_by_username(following_username)
             .await?;
+        // Concurrent follows by the same user wait here, so only one of them inserts.
+        transaction.users().lock_by_id(follower_id).await?;
 
-        let result = self
-            .repository
+        let result = transaction
+            .profiles()
             .is_follow(follower_id, following_user.id())
             .await;
 
@@ -83,13 +99,15 @@ impl ProfileService for ConcreteProfileService {
                     RealWorldResult::Ok(profile)
                 } else {
                     info!("Request Follow");
-                    let follow_user = self
-                        .repository
+                    let follow_user = transaction
+                        .profiles()
                         .follow_user(follower_id, following_user.id())
                         .await;
                     match follow_user {
                         RealWorldResult::Ok(_) => {
+                            transaction.commit().await?;
                             info!("Follow Success {}", following_user.user_name());
+                            counter!(PROFILE_FOLLOWS_TOTAL).increment(1);
                             let profile = Profile::new(
                                 following_user.user_name().to_owned(),
                                 following_user.bio().to_owned(),
@@ -112,6 +130,7 @@ impl ProfileService for ConcreteProfileService {
         }
     }
 
+    #[instrument(skip(self))]
     async fn unfollow(
         &self,
         follower_id: i64,
@@ -124,10 +143,10 @@ impl ProfileService for ConcreteProfileService {
 
         info!("Find follower user {} ", follower_user.user_name());
 
-        let _unfollow = self
-            .repository
+        self.repository
             .unfollow(follower_id, follower_user.id())
-            .await;
+            .await?;
+        counter!(PROFILE_UNFOLLOWS_TOTAL).increment(1);
 
         Ok(Profile::new(
             follower_user.user_name().to_string(),
@@ -137,3 +156,89 @@ impl ProfileService for ConcreteProfileService {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        auth::{hash_password::ArgonHash, jwt_encoder::JwtEncoder},
+        profile::{domain::service::ProfileService, repository::memory::InMemoryProfileRepository},
+        unit_of_work::memory::InMemoryUnitOfWork,
+        user::{
+            domain::{model::UserRegistry, service::UserService},
+            repository::memory::InMemoryUserRepository,
+            service::service::ConcreteUserService,
+        },
+    };
+
+    use super::ConcreteProfileService;
+
+    #[tokio::test]
+    async fn follow_then_unfollow_test() {
+        let user_repository = Arc::new(InMemoryUserRepository::new());
+        let profile_repository = Arc::new(InMemoryProfileRepository::new());
+        let unit_of_work = Arc::new(InMemoryUnitOfWork::new(
+            user_repository.clone(),
+            profile_repository.clone(),
+        ));
+        let user_service = ConcreteUserService::new(
+            user_repository,
+            Arc::new(ArgonHash),
+            Arc::new(JwtEncoder::from(String::from("secret_key"))),
+            unit_of_work.clone(),
+        );
+        for username in ["fan", "celeb"] {
+            let registry = UserRegistry::new(
+                username.to_owned(),
+                format!("{username}@example.com"),
+                String::from("password"),
+            );
+            user_service.registry(registry).await.unwrap();
+        }
+        let service =
+            ConcreteProfileService::new(profile_repository, Arc::new(user_service), unit_of_work);
+        let fan_id = 1;
+
+        let followed = service.follow_user(fan_id, String::from("celeb")).await;
+        let as_fan = service
+            .get_profile(Some(fan_id), String::from("celeb"))
+            .await;
+        let anonymous = service.get_profile(None, String::from("celeb")).await;
+
+        assert!(followed.unwrap().following());
+        assert!(as_fan.unwrap().following());
+        assert!(!anonymous.unwrap().following());
+
+        let unfollowed = service.unfollow(fan_id, String::from("celeb")).await;
+        let as_fan = service
+            .get_profile(Some(fan_id), String::from("celeb"))
+            .await;
+
+        assert!(!unfollowed.unwrap().following());
+        assert!(!as_fan.unwrap().following());
+    }
+
+    #[tokio::test]
+    async fn follow_unknown_user_fails_test() {
+        let user_repository = Arc::new(InMemoryUserRepository::new());
+        let profile_repository = Arc::new(InMemoryProfileRepository::new());
+        let unit_of_work = Arc::new(InMemoryUnitOfWork::new(
+            user_repository.clone(),
+            profile_repository.clone(),
+        ));
+        let user_service = ConcreteUserService::new(
+            user_repository,
+            Arc::new(ArgonHash),
+            Arc::new(JwtEncoder::from(String::from("secret_key"))),
+            unit_of_work.clone(),
+        );
+        let service =
+            ConcreteProfileService::new(profile_repository, Arc::new(user_service), unit_of_work);
+
+        assert!(service
+            .follow_user(1, String::from("nobody"))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use std::sync::{Arc, Mutex};
+
+use axum::async_trait;
+use tracing::warn;
+
+use crate::config::RealWorldResult;
+use crate::profile::domain::repository::DynProfileRepository;
+use crate::user::domain::model::{UserRegistry, UserUpdate};
+use crate::user::domain::repository::{DynUserRepository, UserRepository};
+use crate::user::domain::user::User;
+use crate::user::repository::cache::DynUserCache;
+
+use super::{DynTransaction, DynUnitOfWork, Transaction, UnitOfWork};
+
+/// Evicts users updated inside a transaction from `cache` once it commits, so the
+/// `CachedUserRepository` sharing that cache stops serving the old rows.
+pub struct CachedUnitOfWork {
+    inner: DynUnitOfWork,
+    cache: DynUserCache,
+}
+
+impl CachedUnitOfWork {
+    pub fn new(inner: DynUnitOfWork, cache: DynUserCache) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl UnitOfWork for CachedUnitOfWork {
+    async fn begin(&self) -> RealWorldResult<DynTransaction> {
+        Ok(Box::new(CachedTransaction {
+            inner: self.inner.begin().await?,
+            cache: self.cache.clone(),
+            updated_ids: Arc::new(Mutex::new(Vec::new())),
+        }))
+    }
+}
+
+struct CachedTransaction {
+    inner: DynTransaction,
+    cache: DynUserCache,
+    updated_ids: Arc<Mutex<Vec<i64>>>,
+}
+
+#[async_trait]
+impl Transaction for CachedTransaction {
+    fn users(&self) -> DynUserRepository {
+        Arc::new(UpdateRecorder {
+            inner: self.inner.users(),
+            updated_ids: self.updated_ids.clone(),
+        })
+    }
+
+    fn profiles(&self) -> DynProfileRepository {
+        self.inner.profiles()
+    }
+
+    async fn commit(self: Box<Self>) -> RealWorldResult<()> {
+        self.inner.commit().await?;
+
+        let updated_ids = std::mem::take(&mut *self.updated_ids.lock().unwrap());
+        for id in updated_ids {
+            if let Err(err) = self.cache.invalidate(id).await {
+                warn!("User cache invalidation failed for id {} {}", id, err);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Remembers which users the transaction tried to update, also when it failed.
+struct UpdateRecorder {
+    inner: DynUserRepository,
+    updated_ids: Arc<Mutex<Vec<i64>>>,
+}
+
+#[async_trait]
+impl UserRepository for UpdateRecorder {
+    async fn registry(&self, user_register: UserRegistry) -> RealWorldResult<User> {
+        self.inner.registry(user_register).await
+    }
+
+    async fn is_exist(&self, user_email: String) -> RealWorldResult<bool> {
+        self.inner.is_exist(user_email).await
+    }
+
+    async fn find_by_email(&self, email: String) -> RealWorldResult<User> {
+        self.inner.find_by_email(email).await
+    }
+
+    async fn find_by_id(&self, id: i64) -> RealWorldResult<User> {
+        self.inner.find_by_id(id).await
+    }
+
+    async fn lock_by_id(&self, id: i64) -> RealWorldResult<User> {
+        self.inner.lock_by_id(id).await
+    }
+
+    async fn update(&self, id: i64, user_update: UserUpdate) -> RealWorldResult<User> {
+        self.updated_ids.lock().unwrap().push(id);
+        self.inner.update(id, user_update).await
+    }
+
+    async fn find_by_username(&self, username: String) -> RealWorldResult<User> {
+        self.inner.find_by_username(username).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+
+    use crate::auth::clock::MockClock;
+    use crate::profile::repository::memory::InMemoryProfileRepository;
+    use crate::unit_of_work::memory::InMemoryUnitOfWork;
+    use crate::unit_of_work::UnitOfWork;
+    use crate::user::domain::model::{UserRegistry, UserUpdate};
+    use crate::user::domain::repository::UserRepository;
+    use crate::user::repository::cache::{InMemoryUserCache, UserCache, UserCacheKey};
+    use crate::user::repository::memory::InMemoryUserRepository;
+
+    use super::CachedUnitOfWork;
+
+    #[tokio::test]
+    async fn commit_evicts_updated_users_test() {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let profiles = Arc::new(InMemoryProfileRepository::new());
+        let cache = Arc::new(InMemoryUserCache::new(
+            NonZeroUsize::new(8).unwrap(),
+            Duration::minutes(1),
+            Arc::new(MockClock::new(Utc::now())),
+        ));
+        let unit_of_work = CachedUnitOfWork::new(
+            Arc::new(InMemoryUnitOfWork::new(users.clone(), profiles)),
+            cache.clone(),
+        );
+        let jake = users
+            .registry(UserRegistry::new(
+                String::from("jake"),
+                String::from("jake@example.com"),
+                String::from("hashed-password"),
+            ))
+            .await
+            .unwrap();
+        cache.put(&jake, 0).await.unwrap();
+
+        let transaction = unit_of_work.begin().await.unwrap();
+        let update = UserUpdate::new(
+            Some(String::from("jake@example.com")),
+            Some(String::from("jake")),
+            Some(String::from("hashed-password")),
+            None,
+            Some(String::from("bio")),
+        );
+        transaction.users().update(jake.id(), update).await.unwrap();
+        let before_commit = cache.get(&UserCacheKey::Id(jake.id())).await.unwrap();
+        transaction.commit().await.unwrap();
+        let after_commit = cache.get(&UserCacheKey::Id(jake.id())).await.unwrap();
+
+        assert!(before_commit.is_some());
+        assert!(after_commit.is_none());
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use crate::config::RealWorldResult;
+use crate::profile::domain::repository::DynProfileRepository;
+use crate::profile::repository::memory::{FollowRow, InMemoryProfileRepository};
+use crate::user::domain::repository::DynUserRepository;
+use crate::user::repository::memory::{InMemoryUserRepository, UserTable};
+
+use super::{DynTransaction, Transaction, UnitOfWork};
+
+/// `UnitOfWork` over the in-memory repositories. Transactions run one at a time and
+/// write in place, restoring a snapshot on rollback, so calls made outside of any
+/// transaction can see changes that are not committed yet.
+pub struct InMemoryUnitOfWork {
+    users: Arc<InMemoryUserRepository>,
+    profiles: Arc<InMemoryProfileRepository>,
+    lock: Arc<Mutex<()>>,
+}
+
+impl InMemoryUnitOfWork {
+    pub fn new(
+        users: Arc<InMemoryUserRepository>,
+        profiles: Arc<InMemoryProfileRepository>,
+    ) -> Self {
+        Self {
+            users,
+            profiles,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+}
+
+#[async_trait]
+impl UnitOfWork for InMemoryUnitOfWork {
+    async fn begin(&self) -> RealWorldResult<DynTransaction> {
+        let guard = self.lock.clone().lock_owned().await;
+        Ok(Box::new(InMemoryTransaction {
+            users: self.users.clone(),
+            profiles: self.profiles.clone(),
+            snapshot: Some((self.users.snapshot(), self.profiles.snapshot())),
+            _guard: guard,
+        }))
+    }
+}
+
+pub struct InMemoryTransaction {
+    users: Arc<InMemoryUserRepository>,
+    profiles: Arc<InMemoryProfileRepository>,
+    /// Tables as they were on `begin`, taken away by `commit`.
+    snapshot: Option<(UserTable, Vec<FollowRow>)>,
+    _guard: OwnedMutexGuard<()>,
+}
+
+#[async_trait]
+impl Transaction for InMemoryTransaction {
+    fn users(&self) -> DynUserRepository {
+        self.users.clone()
+    }
+
+    fn profiles(&self) -> DynProfileRepository {
+        self.profiles.clone()
+    }
+
+    async fn commit(mut self: Box<Self>) -> RealWorldResult<()> {
+        self.snapshot = None;
+        Ok(())
+    }
+}
+
+impl Drop for InMemoryTransaction {
+    fn drop(&mut self) {
+        if let Some((users, follows)) = self.snapshot.take() {
+            self.users.restore(users);
+            self.profiles.restore(follows);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::profile::domain::repository::ProfileRepository;
+    use crate::profile::repository::memory::InMemoryProfileRepository;
+    use crate::unit_of_work::UnitOfWork;
+    use crate::user::domain::model::UserRegistry;
+    use crate::user::domain::repository::UserRepository;
+    use crate::user::repository::memory::InMemoryUserRepository;
+
+    use super::InMemoryUnitOfWork;
+
+    #[tokio::test]
+    async fn drop_without_commit_rolls_back_test() {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let profiles = Arc::new(InMemoryProfileRepository::new());
+        let unit_of_work = InMemoryUnitOfWork::new(users.clone(), profiles.clone());
+
+        let committed = unit_of_work.begin().await.unwrap();
+        committed
+            .users()
+            .registry(UserRegistry::new(
+                String::from("jake"),
+                String::from("jake@example.com"),
+                String::from("password"),
+            ))
+            .await
+            .unwrap();
+        committed.commit().await.unwrap();
+        let dropped = unit_of_work.begin().await.unwrap();
+        dropped
+            .users()
+            .registry(UserRegistry::new(
+                String::from("jane"),
+                String::from("jane@example.com"),
+                String::from("password"),
+            ))
+            .await
+            .unwrap();
+        dropped.profiles().follow_user(2, 1).await.unwrap();
+        drop(dropped);
+
+        assert!(users.find_by_username(String::from("jake")).await.is_ok());
+        assert!(users.find_by_username(String::from("jane")).await.is_err());
+        assert!(!profiles.is_follow(2, 1).await.unwrap());
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+
+use crate::config::RealWorldResult;
+use crate::profile::domain::repository::DynProfileRepository;
+use crate::user::domain::repository::DynUserRepository;
+
+pub mod cached;
+pub mod memory;
+pub mod postgres;
+
+pub type DynUnitOfWork = Arc<dyn UnitOfWork + Send + Sync>;
+pub type DynTransaction = Box<dyn Transaction + Send + Sync>;
+
+/// Starts transactions for service operations that read and write through several
+/// repositories and must not interleave with each other.
+#[async_trait]
+pub trait UnitOfWork {
+    async fn begin(&self) -> RealWorldResult<DynTransaction>;
+}
+
+/// Repositories that share one transaction. Their changes become visible together on
+/// `commit`, and are rolled back when the transaction is dropped without it.
+#[async_trait]
+pub trait Transaction {
+    fn users(&self) -> DynUserRepository;
+    fn profiles(&self) -> DynProfileRepository;
+    async fn commit(self: Box<Self>) -> RealWorldResult<()>;
+}
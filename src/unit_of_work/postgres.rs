@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use axum::async_trait;
+use tokio::sync::Mutex;
+use tracing::instrument;
+
+use crate::auth::clock::DynClock;
+use crate::config::db::{DbPool, DbScope, SharedTransaction};
+use crate::config::RealWorldResult;
+use crate::profile::domain::repository::DynProfileRepository;
+use crate::profile::repository::repository::ConcreteProfileRepository;
+use crate::user::domain::repository::DynUserRepository;
+use crate::user::repository::repository::ConcreteUserRepository;
+
+use super::{DynTransaction, Transaction, UnitOfWork};
+
+pub struct ConcreteUnitOfWork {
+    db_pool: DbPool,
+    clock: DynClock,
+}
+
+impl ConcreteUnitOfWork {
+    pub fn new(db_pool: DbPool, clock: DynClock) -> Self {
+        Self { db_pool, clock }
+    }
+}
+
+#[async_trait]
+impl UnitOfWork for ConcreteUnitOfWork {
+    #[instrument(level = "debug", skip_all)]
+    async fn begin(&self) -> RealWorldResult<DynTransaction> {
+        let transaction = self.db_pool.begin().await?;
+        Ok(Box::new(ConcreteTransaction {
+            transaction: Arc::new(Mutex::new(Some(transaction))),
+            clock: self.clock.clone(),
+        }))
+    }
+}
+
+/// Postgres transaction on the primary. Dropping it returns the connection to the
+/// pool, which rolls back whatever was not committed.
+pub struct ConcreteTransaction {
+    transaction: SharedTransaction,
+    clock: DynClock,
+}
+
+impl ConcreteTransaction {
+    fn scope(&self) -> DbScope {
+        DbScope::Transaction(self.transaction.clone())
+    }
+}
+
+#[async_trait]
+impl Transaction for ConcreteTransaction {
+    fn users(&self) -> DynUserRepository {
+        Arc::new(ConcreteUserRepository::in_scope(
+            self.scope(),
+            self.clock.clone(),
+        ))
+    }
+
+    fn profiles(&self) -> DynProfileRepository {
+        Arc::new(ConcreteProfileRepository::in_scope(
+            self.scope(),
+            self.clock.clone(),
+        ))
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn commit(self: Box<Self>) -> RealWorldResult<()> {
+        let transaction = self.transaction.lock().await.take();
+        match transaction {
+            Some(transaction) => Ok(transaction.commit().await?),
+            None => Err(anyhow!("Transaction already committed or rolled back")),
+        }
+    }
+}
@@ -65,6 +65,7 @@ pub struct UserUpdate {
     password: Option<String>,
     image: Option<String>,
     bio: Option<String>,
+    expected_version: Option<i64>,
 }
 
 impl UserUpdate {
@@ -81,6 +82,16 @@ impl UserUpdate {
             password,
             image,
             bio,
+            expected_version: None,
+        }
+    }
+
+    /// Makes the update fail with `AppError::PreconditionFailed` unless the user is
+    /// still at `version`.
+    pub fn expect_version(self, version: Option<i64>) -> Self {
+        Self {
+            expected_version: version,
+            ..self
         }
     }
 
@@ -98,6 +109,7 @@ impl UserUpdate {
             password: Some(password),
             image,
             bio,
+            ..self
         }
     }
 
@@ -120,4 +132,8 @@ impl UserUpdate {
     pub fn bio(&self) -> Option<&String> {
         self.bio.as_ref()
     }
+
+    pub fn expected_version(&self) -> Option<i64> {
+        self.expected_version
+    }
 }
@@ -17,6 +17,9 @@ pub trait UserRepository {
     async fn is_exist(&self, user_email: String) -> RealWorldResult<bool>;
     async fn find_by_email(&self, email: String) -> RealWorldResult<User>;
     async fn find_by_id(&self, id: i64) -> RealWorldResult<User>;
+    /// Like `find_by_id`, but inside a transaction the row stays locked until it
+    /// ends, so concurrent operations on the same user run one after another.
+    async fn lock_by_id(&self, id: i64) -> RealWorldResult<User>;
     async fn update(&self, id: i64, user_update: UserUpdate) -> RealWorldResult<User>;
     async fn find_by_username(&self, username: String) -> RealWorldResult<User>;
 }
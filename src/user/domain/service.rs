@@ -19,4 +19,5 @@ pub trait UserService {
     async fn get_info(&self, id: i64) -> RealWorldResult<AuthUser>;
     async fn update(&self, id: i64, user_update: UserUpdate) -> RealWorldResult<AuthUser>;
     async fn get_info_by_user_name(&self, username: String) -> RealWorldResult<User>;
+    async fn reset_password(&self, email: String, password: String) -> RealWorldResult<AuthUser>;
 }
@@ -6,6 +6,8 @@ pub struct User {
     user_name: String,
     bio: Option<String>,
     image: Option<String>,
+    /// Starts at 1 and grows by one with every update.
+    version: i64,
 }
 
 impl User {
@@ -24,6 +26,7 @@ impl User {
             user_name,
             bio,
             image,
+            version: 1,
         }
     }
 
@@ -45,6 +48,9 @@ impl User {
     pub fn image(&self) -> &Option<String> {
         &self.image
     }
+    pub fn version(&self) -> i64 {
+        self.version
+    }
 
     pub fn set_email(self, email: String) -> User {
         User { email, ..self }
@@ -61,6 +67,9 @@ impl User {
     pub fn set_image(self, image: Option<String>) -> Self {
         User { image, ..self }
     }
+    pub fn set_version(self, version: i64) -> Self {
+        User { version, ..self }
+    }
 }
 
 pub struct AuthUser {
@@ -69,6 +78,7 @@ pub struct AuthUser {
     email: String,
     bio: Option<String>,
     image: Option<String>,
+    version: i64,
 }
 
 impl AuthUser {
@@ -79,6 +89,7 @@ impl AuthUser {
             email: user.email,
             bio: user.bio,
             image: user.image,
+            version: user.version,
         }
     }
 
@@ -101,4 +112,8 @@ impl AuthUser {
     pub fn image(&self) -> Option<String> {
         self.image.as_ref().map(|s| s.to_owned())
     }
+
+    pub fn version(&self) -> i64 {
+        self.version
+    }
 }
@@ -4,15 +4,23 @@ use axum::{
 };
 use user_handler::{get_info_api, login_api, register_api, update_user_api};
 
+use crate::config::container::AppContainer;
+use crate::middleware::rate_limit::{ArcRateLimiter, RateLimitGroup};
+
 pub mod domain;
 pub mod repository;
 pub mod service;
 pub mod user_handler;
 
-pub fn user_route() -> Router {
-    Router::new()
+pub fn user_route(rate_limiter: &ArcRateLimiter) -> Router<AppContainer> {
+    let auth_route = Router::new()
         .route("/users", post(register_api))
         .route("/users/login", post(login_api))
+        .route_layer(rate_limiter.layer(RateLimitGroup::Auth));
+
+    Router::new()
         .route("/user", get(get_info_api))
         .route("/user", put(update_user_api))
+        .route_layer(rate_limiter.layer(RateLimitGroup::Default))
+        .merge(auth_route)
 }
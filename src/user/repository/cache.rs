@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use axum::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use lru::LruCache;
+
+use crate::auth::clock::DynClock;
+use crate::config::RealWorldResult;
+use crate::user::domain::user::User;
+
+pub type DynUserCache = Arc<dyn UserCache + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum UserCacheKey {
+    Id(i64),
+    Username(String),
+}
+
+/// Where `CachedUserRepository` keeps users it has read. The in-process cache serves
+/// only one instance; a shared cache lets several instances invalidate each other.
+#[async_trait]
+pub trait UserCache {
+    async fn get(&self, key: &UserCacheKey) -> RealWorldResult<Option<User>>;
+    /// Counter bumped by every `invalidate`; read it before loading a user to `put`.
+    async fn epoch(&self) -> RealWorldResult<u64>;
+    /// Stores `user` under its id and its username, unless the user was invalidated
+    /// after `epoch`, in which case the loaded row may predate that write.
+    async fn put(&self, user: &User, epoch: u64) -> RealWorldResult<()>;
+    /// Forgets the user with `id` under every key.
+    async fn invalidate(&self, id: i64) -> RealWorldResult<()>;
+}
+
+/// LRU cache in process memory whose entries also expire `ttl` after being stored.
+pub struct InMemoryUserCache {
+    entries: Mutex<Entries>,
+    ttl: Duration,
+    clock: DynClock,
+}
+
+struct Entries {
+    by_id: LruCache<i64, Entry>,
+    /// Username to id of every entry in `by_id`.
+    ids: HashMap<String, i64>,
+    epoch: u64,
+    /// Epoch of the latest invalidation per id, for as many ids as `by_id` holds.
+    invalidated: LruCache<i64, u64>,
+    /// Latest epoch pushed out of `invalidated`, assumed for every id not in it.
+    forgotten_epoch: u64,
+}
+
+struct Entry {
+    user: User,
+    expires_at: DateTime<Utc>,
+}
+
+impl InMemoryUserCache {
+    pub fn new(capacity: NonZeroUsize, ttl: Duration, clock: DynClock) -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                by_id: LruCache::new(capacity),
+                ids: HashMap::new(),
+                epoch: 0,
+                invalidated: LruCache::new(capacity),
+                forgotten_epoch: 0,
+            }),
+            ttl,
+            clock,
+        }
+    }
+}
+
+impl Entries {
+    fn remove(&mut self, id: i64) {
+        if let Some(entry) = self.by_id.pop(&id) {
+            self.ids.remove(entry.user.user_name());
+        }
+    }
+}
+
+#[async_trait]
+impl UserCache for InMemoryUserCache {
+    async fn get(&self, key: &UserCacheKey) -> RealWorldResult<Option<User>> {
+        let mut entries = self.entries.lock().unwrap();
+        let id = match key {
+            UserCacheKey::Id(id) => *id,
+            UserCacheKey::Username(username) => match entries.ids.get(username) {
+                Some(id) => *id,
+                None => return Ok(None),
+            },
+        };
+
+        let expired = match entries.by_id.get(&id) {
+            Some(entry) if entry.expires_at > self.clock.now() => {
+                return Ok(Some(entry.user.clone()))
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            entries.remove(id);
+        }
+        Ok(None)
+    }
+
+    async fn epoch(&self) -> RealWorldResult<u64> {
+        Ok(self.entries.lock().unwrap().epoch)
+    }
+
+    async fn put(&self, user: &User, epoch: u64) -> RealWorldResult<()> {
+        let mut entries = self.entries.lock().unwrap();
+        let invalidated_at = match entries.invalidated.peek(&user.id()) {
+            Some(invalidated_at) => *invalidated_at,
+            None => entries.forgotten_epoch,
+        };
+        if invalidated_at > epoch {
+            return Ok(());
+        }
+        // Drops the old username too, in case the user was renamed.
+        entries.remove(user.id());
+
+        let entry = Entry {
+            user: user.clone(),
+            expires_at: self.clock.now() + self.ttl,
+        };
+        if let Some((_, evicted)) = entries.by_id.push(user.id(), entry) {
+            entries.ids.remove(evicted.user.user_name());
+        }
+        entries.ids.insert(user.user_name().to_owned(), user.id());
+        Ok(())
+    }
+
+    async fn invalidate(&self, id: i64) -> RealWorldResult<()> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(id);
+        entries.epoch += 1;
+        let epoch = entries.epoch;
+        if let Some((evicted_id, evicted_epoch)) = entries.invalidated.push(id, epoch) {
+            if evicted_id != id {
+                entries.forgotten_epoch = evicted_epoch;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::sync::Arc;
+
+    use chrono::{Duration, TimeZone, Utc};
+
+    use crate::auth::clock::MockClock;
+    use crate::user::domain::user::User;
+
+    use super::{InMemoryUserCache, UserCache, UserCacheKey};
+
+    #[tokio::test]
+    async fn entries_expire_and_least_recent_is_evicted_test() {
+        let clock = Arc::new(MockClock::new(
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        ));
+        let cache = InMemoryUserCache::new(
+            NonZeroUsize::new(2).unwrap(),
+            Duration::seconds(30),
+            clock.clone(),
+        );
+        let jake = User::new(
+            1,
+            String::from("jake@example.com"),
+            String::from("hashed-password"),
+            String::from("jake"),
+            None,
+            None,
+        );
+        let jane = User::new(
+            2,
+            String::from("jane@example.com"),
+            String::from("hashed-password"),
+            String::from("jane"),
+            None,
+            None,
+        );
+        let john = User::new(
+            3,
+            String::from("john@example.com"),
+            String::from("hashed-password"),
+            String::from("john"),
+            None,
+            None,
+        );
+
+        cache.put(&jake, 0).await.unwrap();
+        cache.put(&jane, 0).await.unwrap();
+        cache.get(&UserCacheKey::Id(1)).await.unwrap();
+        cache.put(&john, 0).await.unwrap();
+
+        assert!(cache
+            .get(&UserCacheKey::Username(String::from("jake")))
+            .await
+            .unwrap()
+            .is_some());
+        assert!(cache
+            .get(&UserCacheKey::Username(String::from("jane")))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(cache.get(&UserCacheKey::Id(2)).await.unwrap().is_none());
+
+        clock.advance(Duration::seconds(30));
+        assert!(cache.get(&UserCacheKey::Id(1)).await.unwrap().is_none());
+        assert!(cache
+            .get(&UserCacheKey::Username(String::from("jake")))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn rename_and_invalidate_drop_every_key_test() {
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let cache =
+            InMemoryUserCache::new(NonZeroUsize::new(8).unwrap(), Duration::minutes(1), clock);
+        let jake = User::new(
+            1,
+            String::from("jake@example.com"),
+            String::from("hashed-password"),
+            String::from("jake"),
+            None,
+            None,
+        );
+        let jacob = User::new(
+            1,
+            String::from("jacob@example.com"),
+            String::from("hashed-password"),
+            String::from("jacob"),
+            None,
+            None,
+        );
+
+        cache.put(&jake, 0).await.unwrap();
+        cache.put(&jacob, 0).await.unwrap();
+
+        assert!(cache
+            .get(&UserCacheKey::Username(String::from("jake")))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(cache
+            .get(&UserCacheKey::Username(String::from("jacob")))
+            .await
+            .unwrap()
+            .is_some());
+
+        cache.invalidate(1).await.unwrap();
+        assert!(cache.get(&UserCacheKey::Id(1)).await.unwrap().is_none());
+        assert!(cache
+            .get(&UserCacheKey::Username(String::from("jacob")))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn put_loaded_before_invalidation_is_skipped_test() {
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let cache =
+            InMemoryUserCache::new(NonZeroUsize::new(1).unwrap(), Duration::minutes(1), clock);
+        let jake = User::new(
+            1,
+            String::from("jake@example.com"),
+            String::from("hashed-password"),
+            String::from("jake"),
+            None,
+            None,
+        );
+
+        let before_updates = cache.epoch().await.unwrap();
+        cache.invalidate(1).await.unwrap();
+        // Pushes the invalidation of 1 out, so only its epoch bound is left.
+        cache.invalidate(2).await.unwrap();
+        cache.put(&jake, before_updates).await.unwrap();
+        assert!(cache.get(&UserCacheKey::Id(1)).await.unwrap().is_none());
+
+        let after_updates = cache.epoch().await.unwrap();
+        cache.put(&jake, after_updates).await.unwrap();
+        assert!(cache.get(&UserCacheKey::Id(1)).await.unwrap().is_some());
+    }
+}
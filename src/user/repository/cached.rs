@@ -0,0 +1,165 @@
+use std::future::Future;
+
+use axum::async_trait;
+use metrics::counter;
+use tracing::{instrument, warn};
+
+use crate::{
+    config::{metrics::USER_CACHE_REQUESTS_TOTAL, RealWorldResult},
+    user::domain::{
+        model::{UserRegistry, UserUpdate},
+        repository::{DynUserRepository, UserRepository},
+        user::User,
+    },
+};
+
+use super::cache::{DynUserCache, UserCacheKey};
+
+/// Serves `find_by_id` and `find_by_username` from `cache`, and forgets a user once it
+/// is updated through this repository. Other lookups and `lock_by_id` always reach
+/// `inner`. Transactions evict what they update through `CachedUnitOfWork`; changes
+/// made elsewhere, e.g. by another instance with its own cache, show up once the entry
+/// expires.
+pub struct CachedUserRepository {
+    inner: DynUserRepository,
+    cache: DynUserCache,
+}
+
+impl CachedUserRepository {
+    pub fn new(inner: DynUserRepository, cache: DynUserCache) -> Self {
+        Self { inner, cache }
+    }
+
+    async fn cached(
+        &self,
+        key: UserCacheKey,
+        load: impl Future<Output = RealWorldResult<User>> + Send,
+    ) -> RealWorldResult<User> {
+        match self.cache.get(&key).await {
+            Ok(Some(user)) => {
+                counter!(USER_CACHE_REQUESTS_TOTAL, "result" => "hit").increment(1);
+                return Ok(user);
+            }
+            Ok(None) => {}
+            // A broken cache only costs a database read.
+            Err(err) => warn!("User cache read failed for {:?} {}", key, err),
+        }
+        counter!(USER_CACHE_REQUESTS_TOTAL, "result" => "miss").increment(1);
+
+        // Taken before the load, so an update that lands while loading keeps the row
+        // it may have overtaken out of the cache.
+        let epoch = self.cache.epoch().await;
+        let user = load.await?;
+        match epoch {
+            Ok(epoch) => {
+                if let Err(err) = self.cache.put(&user, epoch).await {
+                    warn!("User cache write failed for {:?} {}", key, err);
+                }
+            }
+            Err(err) => warn!("User cache epoch read failed for {:?} {}", key, err),
+        }
+        Ok(user)
+    }
+}
+
+#[async_trait]
+impl UserRepository for CachedUserRepository {
+    async fn registry(&self, user_register: UserRegistry) -> RealWorldResult<User> {
+        self.inner.registry(user_register).await
+    }
+
+    async fn is_exist(&self, user_email: String) -> RealWorldResult<bool> {
+        self.inner.is_exist(user_email).await
+    }
+
+    async fn find_by_email(&self, email: String) -> RealWorldResult<User> {
+        self.inner.find_by_email(email).await
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_id(&self, id: i64) -> RealWorldResult<User> {
+        self.cached(UserCacheKey::Id(id), self.inner.find_by_id(id))
+            .await
+    }
+
+    async fn lock_by_id(&self, id: i64) -> RealWorldResult<User> {
+        self.inner.lock_by_id(id).await
+    }
+
+    #[instrument(level = "debug", skip(self, user_update))]
+    async fn update(&self, id: i64, user_update: UserUpdate) -> RealWorldResult<User> {
+        let result = self.inner.update(id, user_update).await;
+        // Also on failure: a rejected write may mean the cached copy is outdated.
+        if let Err(err) = self.cache.invalidate(id).await {
+            warn!("User cache invalidation failed for id {} {}", id, err);
+        }
+        result
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    async fn find_by_username(&self, username: String) -> RealWorldResult<User> {
+        self.cached(
+            UserCacheKey::Username(username.clone()),
+            self.inner.find_by_username(username),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+
+    use crate::auth::clock::MockClock;
+    use crate::user::domain::model::{UserRegistry, UserUpdate};
+    use crate::user::domain::repository::UserRepository;
+    use crate::user::repository::cache::InMemoryUserCache;
+    use crate::user::repository::memory::InMemoryUserRepository;
+
+    use super::CachedUserRepository;
+
+    #[tokio::test]
+    async fn update_invalidates_cached_user_test() {
+        let inner = Arc::new(InMemoryUserRepository::new());
+        let cache = InMemoryUserCache::new(
+            NonZeroUsize::new(8).unwrap(),
+            Duration::minutes(1),
+            Arc::new(MockClock::new(Utc::now())),
+        );
+        let repository = CachedUserRepository::new(inner.clone(), Arc::new(cache));
+        let jake = repository
+            .registry(UserRegistry::new(
+                String::from("jake"),
+                String::from("jake@example.com"),
+                String::from("hashed-password"),
+            ))
+            .await
+            .unwrap();
+
+        repository.find_by_id(jake.id()).await.unwrap();
+        let update = UserUpdate::new(
+            Some(String::from("jake@example.com")),
+            Some(String::from("jacob")),
+            Some(String::from("hashed-password")),
+            None,
+            Some(String::from("bio")),
+        );
+        repository.update(jake.id(), update).await.unwrap();
+        let updated = repository.find_by_id(jake.id()).await.unwrap();
+        inner.soft_delete(jake.id());
+
+        assert_eq!(updated.bio(), &Some(String::from("bio")));
+        assert!(repository.find_by_id(jake.id()).await.is_ok());
+        assert!(repository
+            .find_by_username(String::from("jacob"))
+            .await
+            .is_ok());
+        assert!(repository
+            .find_by_username(String::from("jake"))
+            .await
+            .is_err());
+    }
+}
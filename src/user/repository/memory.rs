@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use anyhow::anyhow;
+use axum::async_trait;
+
+use crate::{
+    config::{error::AppError, RealWorldResult},
+    user::domain::{
+        model::{UserRegistry, UserUpdate},
+        repository::UserRepository,
+        user::User,
+    },
+};
+
+/// `UserRepository` kept in process memory, for service tests that should not need
+/// Postgres. Mirrors the SQL repository: email and username are unique across all
+/// rows, and soft deleted users are hidden from every lookup except `is_exist`.
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    table: RwLock<UserTable>,
+}
+
+#[derive(Default, Clone)]
+pub(crate) struct UserTable {
+    last_id: i64,
+    rows: BTreeMap<i64, UserRow>,
+}
+
+#[derive(Clone)]
+struct UserRow {
+    user: User,
+    deleted: bool,
+}
+
+impl UserTable {
+    fn find(&self, predicate: impl Fn(&User) -> bool) -> Option<&User> {
+        self.rows
+            .values()
+            .filter(|row| !row.deleted)
+            .map(|row| &row.user)
+            .find(|user| predicate(user))
+    }
+
+    fn check_unique(&self, id: Option<i64>, email: &str, username: &str) -> RealWorldResult<()> {
+        let conflict = self.rows.values().find(|row| {
+            Some(row.user.id()) != id
+                && (row.user.email() == email || row.user.user_name() == username)
+        });
+        match conflict {
+            Some(_) => Err(anyhow!("duplicate key value violates unique constraint")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl InMemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy of every row, for `InMemoryUnitOfWork` to roll back to.
+    pub(crate) fn snapshot(&self) -> UserTable {
+        self.table.read().unwrap().clone()
+    }
+
+    pub(crate) fn restore(&self, table: UserTable) {
+        *self.table.write().unwrap() = table;
+    }
+
+    /// Flags the user as deleted, like `UPDATE users SET deleted = true`.
+    pub fn soft_delete(&self, id: i64) {
+        if let Some(row) = self.table.write().unwrap().rows.get_mut(&id) {
+            row.deleted = true;
+        }
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn registry(&self, user_register: UserRegistry) -> RealWorldResult<User> {
+        let mut table = self.table.write().unwrap();
+        table
+            .check_unique(None, user_register.email(), user_register.user_name())
+            .map_err(|err| anyhow!("User Insert Failed {}", err))?;
+
+        table.last_id += 1;
+        let user = User::new(
+            table.last_id,
+            user_register.email().to_owned(),
+            user_register.password().to_owned(),
+            user_register.user_name().to_owned(),
+            None,
+            None,
+        );
+        table.rows.insert(
+            user.id(),
+            UserRow {
+                user: user.clone(),
+                deleted: false,
+            },
+        );
+        Ok(user)
+    }
+
+    async fn is_exist(&self, user_email: String) -> RealWorldResult<bool> {
+        let table = self.table.read().unwrap();
+        Ok(table
+            .rows
+            .values()
+            .any(|row| *row.user.email() == user_email))
+    }
+
+    async fn find_by_email(&self, email: String) -> RealWorldResult<User> {
+        let table = self.table.read().unwrap();
+        match table.find(|user| *user.email() == email) {
+            Some(user) => Ok(user.clone()),
+            None => Err(anyhow!("Failed find user")),
+        }
+    }
+
+    async fn find_by_id(&self, id: i64) -> RealWorldResult<User> {
+        let table = self.table.read().unwrap();
+        match table.find(|user| user.id() == id) {
+            Some(user) => Ok(user.clone()),
+            None => Err(anyhow!("Failed find user")),
+        }
+    }
+
+    async fn lock_by_id(&self, id: i64) -> RealWorldResult<User> {
+        // Transactions over this repository already run one at a time.
+        self.find_by_id(id).await
+    }
+
+    async fn update(&self, id: i64, user_update: UserUpdate) -> RealWorldResult<User> {
+        let mut table = self.table.write().unwrap();
+        let Some(version) = table.find(|user| user.id() == id).map(User::version) else {
+            return Err(anyhow!("Failed find user"));
+        };
+        if user_update
+            .expected_version()
+            .is_some_and(|expected| expected != version)
+        {
+            return Err(AppError::PreconditionFailed.into());
+        }
+
+        let (Some(email), Some(username), Some(password)) = (
+            user_update.email(),
+            user_update.username(),
+            user_update.password(),
+        ) else {
+            return Err(anyhow!("null value violates not-null constraint"));
+        };
+        table.check_unique(Some(id), email, username)?;
+
+        let user = User::new(
+            id,
+            email.to_owned(),
+            password.to_owned(),
+            username.to_owned(),
+            user_update.bio().cloned(),
+            user_update.image().cloned(),
+        )
+        .set_version(version + 1);
+        table.rows.insert(
+            id,
+            UserRow {
+                user: user.clone(),
+                deleted: false,
+            },
+        );
+        Ok(user)
+    }
+
+    async fn find_by_username(&self, username: String) -> RealWorldResult<User> {
+        let table = self.table.read().unwrap();
+        match table.find(|user| *user.user_name() == username) {
+            Some(user) => Ok(user.clone()),
+            None => Err(anyhow!("Not Found Data {}", username)),
+        }
+    }
+}
@@ -1 +1,4 @@
+pub mod cache;
+pub mod cached;
+pub mod memory;
 pub mod repository;
@@ -2,9 +2,15 @@ use anyhow::{anyhow, Ok};
 use axum::async_trait;
 use chrono::NaiveDateTime;
 use sqlx::prelude::FromRow;
+use tracing::instrument;
 
 use crate::{
-    config::{db::DbPool, RealWorldResult},
+    auth::clock::DynClock,
+    config::{
+        db::{DbPool, DbScope},
+        error::AppError,
+        RealWorldResult,
+    },
     user::domain::{
         model::{UserRegistry, UserUpdate},
         repository::UserRepository,
@@ -13,35 +19,44 @@ use crate::{
 };
 
 pub struct ConcreteUserRepository {
-    db_pool: DbPool,
+    db: DbScope,
+    clock: DynClock,
 }
 
 impl ConcreteUserRepository {
-    pub fn new(db_pool: DbPool) -> Self {
-        Self { db_pool }
+    pub fn new(db_pool: DbPool, clock: DynClock) -> Self {
+        Self::in_scope(DbScope::Pool(db_pool), clock)
+    }
+
+    /// Repository whose statements run in `db`, e.g. a unit of work's transaction.
+    pub fn in_scope(db: DbScope, clock: DynClock) -> Self {
+        Self { db, clock }
     }
 }
 
 #[async_trait]
 impl UserRepository for ConcreteUserRepository {
+    #[instrument(level = "debug", skip_all, fields(email = %user_register.email()))]
     async fn registry(&self, user_register: UserRegistry) -> RealWorldResult<User> {
         let result = sqlx::query_as!(
             UserEntity,
-            "INSERT INTO users (email, username, password)
-            VALUES ($1, $2, $3)
+            "INSERT INTO users (email, username, password, registration_date, modified_date)
+            VALUES ($1, $2, $3, $4, $4)
             RETURNING *
              ",
             user_register.email(),
             user_register.user_name(),
-            user_register.password()
+            user_register.password(),
+            self.clock.now().naive_utc()
         )
-        .fetch_one(&self.db_pool)
+        .fetch_one(&mut *self.db.writer().await?)
         .await
         .map_err(|err| anyhow!("User Insert Failed {}", err))?;
 
         Ok(result.to_user())
     }
 
+    #[instrument(level = "debug", skip(self))]
     async fn is_exist(&self, user_email: String) -> RealWorldResult<bool> {
         let result = sqlx::query!(
             r#"
@@ -51,12 +66,13 @@ impl UserRepository for ConcreteUserRepository {
         "#,
             user_email
         )
-        .fetch_optional(&self.db_pool)
+        .fetch_optional(&mut *self.db.writer().await?)
         .await?;
 
         RealWorldResult::Ok(result.is_some())
     }
 
+    #[instrument(level = "debug", skip(self))]
     async fn find_by_email(&self, email: String) -> RealWorldResult<User> {
         let result = sqlx::query_as!(
             UserEntity,
@@ -67,7 +83,7 @@ impl UserRepository for ConcreteUserRepository {
             ",
             email
         )
-        .fetch_optional(&self.db_pool)
+        .fetch_optional(&mut *self.db.reader().await?)
         .await?;
 
         let user = match result {
@@ -78,6 +94,7 @@ impl UserRepository for ConcreteUserRepository {
         Ok(user)
     }
 
+    #[instrument(level = "debug", skip(self))]
     async fn find_by_id(&self, id: i64) -> RealWorldResult<User> {
         let result = sqlx::query_as!(
             UserEntity,
@@ -88,7 +105,7 @@ impl UserRepository for ConcreteUserRepository {
             ",
             id
         )
-        .fetch_optional(&self.db_pool)
+        .fetch_optional(&mut *self.db.reader().await?)
         .await?;
         let user = match result {
             Some(user_entity) => user_entity.to_user(),
@@ -97,6 +114,28 @@ impl UserRepository for ConcreteUserRepository {
 
         Ok(user)
     }
+    #[instrument(level = "debug", skip(self))]
+    async fn lock_by_id(&self, id: i64) -> RealWorldResult<User> {
+        let result = sqlx::query_as!(
+            UserEntity,
+            "SELECT *
+            FROM users
+            WHERE id = $1
+            AND deleted = false
+            FOR UPDATE
+            ",
+            id
+        )
+        .fetch_optional(&mut *self.db.writer().await?)
+        .await?;
+
+        match result {
+            Some(user_entity) => Ok(user_entity.to_user()),
+            None => Err(anyhow!("Failed find user")),
+        }
+    }
+
+    #[instrument(level = "debug", skip(self, user_update))]
     async fn update(&self, id: i64, user_update: UserUpdate) -> RealWorldResult<User> {
         let result = sqlx::query_as!(
             UserEntity,
@@ -107,9 +146,12 @@ impl UserRepository for ConcreteUserRepository {
                 username = $2,
                 password = $3,
                 image = $4,
-                bio = $5
-            WHERE id = $6
+                bio = $5,
+                modified_date = $6,
+                version = version + 1
+            WHERE id = $7
             AND deleted = false
+            AND ($8::BIGINT IS NULL OR version = $8)
             RETURNING *
             "#,
             user_update.email(),
@@ -117,14 +159,35 @@ impl UserRepository for ConcreteUserRepository {
             user_update.password(),
             user_update.image(),
             user_update.bio(),
-            id
+            self.clock.now().naive_utc(),
+            id,
+            user_update.expected_version()
         )
-        .fetch_one(&self.db_pool)
+        .fetch_optional(&mut *self.db.writer().await?)
         .await?;
+        if let Some(user_entity) = result {
+            return Ok(user_entity.to_user());
+        }
 
-        Ok(result.to_user())
+        // Nothing was updated: either the user is gone or its version moved on.
+        let exists = match user_update.expected_version() {
+            Some(_) => sqlx::query_scalar!(
+                "SELECT EXISTS(SELECT 1 FROM users WHERE id = $1 AND deleted = false)",
+                id
+            )
+            .fetch_one(&mut *self.db.writer().await?)
+            .await?
+            .unwrap_or(false),
+            None => false,
+        };
+        if exists {
+            Err(AppError::PreconditionFailed.into())
+        } else {
+            Err(anyhow!("Failed find user"))
+        }
     }
 
+    #[instrument(level = "debug", skip(self))]
     async fn find_by_username(&self, username: String) -> RealWorldResult<User> {
         let optional_entity = sqlx::query_as!(
             UserEntity,
@@ -135,7 +198,7 @@ impl UserRepository for ConcreteUserRepository {
             ",
             &username
         )
-        .fetch_optional(&self.db_pool)
+        .fetch_optional(&mut *self.db.reader().await?)
         .await?;
 
         match optional_entity {
@@ -156,6 +219,7 @@ struct UserEntity {
     registration_date: NaiveDateTime,
     modified_date: NaiveDateTime,
     deleted: bool,
+    version: i64,
 }
 
 impl UserEntity {
@@ -168,5 +232,6 @@ impl UserEntity {
             self.bio,
             self.image,
         )
+        .set_version(self.version)
     }
 }
@@ -1,13 +1,16 @@
-use std::borrow::Borrow;
-
 use anyhow::anyhow;
 use axum::async_trait;
-use log::{error, info};
-use validator::ValidateRequired;
+use metrics::counter;
+use tracing::{error, info, instrument};
 
 use crate::{
     auth::{hash_password::DynHashPassword, jwt_encoder::ArcJwtEncoder},
-    config::RealWorldResult,
+    config::{
+        error::AppError,
+        metrics::{USER_LOGINS_TOTAL, USER_REGISTRATIONS_TOTAL},
+        RealWorldResult,
+    },
+    unit_of_work::DynUnitOfWork,
     user::domain::{
         model::{UserLogin, UserRegistry, UserUpdate},
         repository::DynUserRepository,
@@ -20,6 +23,7 @@ pub struct ConcreteUserService {
     repository: DynUserRepository,
     hash_password: DynHashPassword,
     jwt_encoder: ArcJwtEncoder,
+    unit_of_work: DynUnitOfWork,
 }
 
 impl ConcreteUserService {
@@ -27,17 +31,20 @@ impl ConcreteUserService {
         repository: DynUserRepository,
         hash_password: DynHashPassword,
         jwt_encoder: ArcJwtEncoder,
+        unit_of_work: DynUnitOfWork,
     ) -> Self {
         Self {
             repository,
             hash_password,
             jwt_encoder,
+            unit_of_work,
         }
     }
 }
 
 #[async_trait]
 impl UserService for ConcreteUserService {
+    #[instrument(skip_all, fields(email = %user_registry.email()))]
     async fn registry(&self, user_registry: UserRegistry) -> RealWorldResult<AuthUser> {
         let hashed_user_registry = user_registry.hash_of_password(&self.hash_password)?;
         let user = self.repository.registry(hashed_user_registry).await;
@@ -45,6 +52,7 @@ impl UserService for ConcreteUserService {
         let user = match user {
             RealWorldResult::Ok(user) => {
                 info!("[UserRegister] register succeed email is {}", user.email());
+                counter!(USER_REGISTRATIONS_TOTAL).increment(1);
                 user
             }
             RealWorldResult::Err(err) => {
@@ -58,6 +66,7 @@ impl UserService for ConcreteUserService {
         RealWorldResult::Ok(auth_user)
     }
 
+    #[instrument(skip(self))]
     async fn is_exist(&self, email: String) -> RealWorldResult<()> {
         let is_exist = self.repository.is_exist(email.clone()).await?;
 
@@ -69,6 +78,7 @@ impl UserService for ConcreteUserService {
         }
     }
 
+    #[instrument(skip_all, fields(email = %login.email()))]
     async fn login(&self, login: UserLogin) -> RealWorldResult<AuthUser> {
         let user = self
             .repository
@@ -82,6 +92,7 @@ impl UserService for ConcreteUserService {
             }
             Err(_err) => {
                 error!("Failed get user info email is {}", login.email());
+                counter!(USER_LOGINS_TOTAL, "result" => "failed").increment(1);
                 return Err(anyhow!("The email does not exist."));
             }
         };
@@ -89,14 +100,17 @@ impl UserService for ConcreteUserService {
         let verify = self.hash_password.verify(login.password(), user.password());
         if !verify {
             error!("Password is not matched. please check again.");
+            counter!(USER_LOGINS_TOTAL, "result" => "failed").increment(1);
             return Err(anyhow!("Password is not matched. please check again."));
         }
 
         let token = self.jwt_encoder.create_token(&user)?;
+        counter!(USER_LOGINS_TOTAL, "result" => "succeeded").increment(1);
 
         RealWorldResult::Ok(AuthUser::new(user, token))
     }
 
+    #[instrument(skip(self))]
     async fn get_info(&self, id: i64) -> RealWorldResult<AuthUser> {
         let user = self.repository.find_by_id(id).await?;
 
@@ -105,16 +119,29 @@ impl UserService for ConcreteUserService {
         RealWorldResult::Ok(AuthUser::new(user, token))
     }
 
+    #[instrument(skip(self, request))]
     async fn update(&self, id: i64, request: UserUpdate) -> RealWorldResult<AuthUser> {
-        let user = self.repository.find_by_id(id).await?;
-        let updated_email = request.email().unwrap_or(user.email()).to_owned();
-        let updated_username = request.username().unwrap_or(user.user_name()).to_owned();
-        let mut updated_hashed_password = user.password().clone();
+        // Hashed up front so the row is not locked any longer than needed.
+        let hashed_password = match request.password() {
+            Some(password) => Some(self.hash_password.hash(password)?),
+            None => None,
+        };
 
-        if request.password().is_some() {
-            updated_hashed_password = self.hash_password.hash(request.password().unwrap())?;
+        let transaction = self.unit_of_work.begin().await?;
+        // The update is merged onto this row, which stays locked until the commit so
+        // the version check and the update cannot interleave with another update.
+        let user = transaction.users().lock_by_id(id).await?;
+        if request
+            .expected_version()
+            .is_some_and(|version| version != user.version())
+        {
+            return Err(AppError::PreconditionFailed.into());
         }
 
+        let updated_email = request.email().unwrap_or(user.email()).to_owned();
+        let updated_username = request.username().unwrap_or(user.user_name()).to_owned();
+        let updated_hashed_password = hashed_password.unwrap_or_else(|| user.password().clone());
+
         let updated_image = if let Some(image) = request.image() {
             Some(image.to_owned())
         } else {
@@ -135,13 +162,15 @@ impl UserService for ConcreteUserService {
             updated_bio,
         );
 
-        let updated_user = self.repository.update(id, request).await?;
+        let updated_user = transaction.users().update(id, request).await?;
+        transaction.commit().await?;
 
         let token = self.jwt_encoder.create_token(&updated_user)?;
 
         RealWorldResult::Ok(AuthUser::new(updated_user, token))
     }
 
+    #[instrument(skip(self))]
     async fn get_info_by_user_name(&self, username: String) -> RealWorldResult<User> {
         info!("[Get User Info by Username {}]", &username);
 
@@ -158,4 +187,105 @@ impl UserService for ConcreteUserService {
             }
         }
     }
+
+    #[instrument(skip(self, password))]
+    async fn reset_password(&self, email: String, password: String) -> RealWorldResult<AuthUser> {
+        let user = self.repository.find_by_email(email).await?;
+        let user_update = UserUpdate::new(None, None, Some(password), None, None);
+
+        let auth_user = self.update(user.id(), user_update).await?;
+        info!("[ResetPassword] password reset for user id {}", user.id());
+        RealWorldResult::Ok(auth_user)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        auth::{hash_password::ArgonHash, jwt_encoder::JwtEncoder},
+        config::error::AppError,
+        profile::repository::memory::InMemoryProfileRepository,
+        unit_of_work::memory::InMemoryUnitOfWork,
+        user::{
+            domain::{
+                model::{UserLogin, UserRegistry, UserUpdate},
+                service::UserService,
+            },
+            repository::memory::InMemoryUserRepository,
+        },
+    };
+
+    use super::ConcreteUserService;
+
+    /// In-memory service with jake registered, password `password`.
+    async fn service() -> ConcreteUserService {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let profiles = Arc::new(InMemoryProfileRepository::new());
+        let service = ConcreteUserService::new(
+            users.clone(),
+            Arc::new(ArgonHash),
+            Arc::new(JwtEncoder::from(String::from("secret_key"))),
+            Arc::new(InMemoryUnitOfWork::new(users, profiles)),
+        );
+        let registry = UserRegistry::new(
+            String::from("jake"),
+            String::from("jake@jake.jake"),
+            String::from("password"),
+        );
+        service.registry(registry).await.unwrap();
+        service
+    }
+
+    #[tokio::test]
+    async fn registered_user_can_login_test() {
+        let service = service().await;
+
+        let login = UserLogin::new(String::from("jake@jake.jake"), String::from("password"));
+        let wrong = UserLogin::new(String::from("jake@jake.jake"), String::from("wrong"));
+
+        assert_eq!(service.login(login).await.unwrap().username(), "jake");
+        assert!(service.login(wrong).await.is_err());
+        assert!(service
+            .is_exist(String::from("jake@jake.jake"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn update_keeps_fields_not_given_test() {
+        let service = service().await;
+        let update = UserUpdate::new(None, None, None, None, Some(String::from("bio")));
+
+        let updated = service.update(1, update).await.unwrap();
+
+        assert_eq!(updated.email(), "jake@jake.jake");
+        assert_eq!(updated.username(), "jake");
+        assert_eq!(updated.bio(), Some(String::from("bio")));
+    }
+
+    #[tokio::test]
+    async fn update_with_stale_version_fails_test() {
+        let service = service().await;
+        let bio = |bio: &str| UserUpdate::new(None, None, None, None, Some(bio.to_owned()));
+
+        let first = service
+            .update(1, bio("first").expect_version(Some(1)))
+            .await
+            .unwrap();
+        let stale = service
+            .update(1, bio("second").expect_version(Some(1)))
+            .await;
+
+        assert_eq!(first.version(), 2);
+        assert!(matches!(
+            stale.err().map(|err| err.downcast::<AppError>()),
+            Some(Ok(AppError::PreconditionFailed))
+        ));
+        assert_eq!(
+            service.get_info(1).await.unwrap().bio(),
+            Some(String::from("first"))
+        );
+    }
 }
@@ -1,5 +1,8 @@
-use axum::{Extension, Json};
+use axum::extract::State;
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::Json;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use validator::Validate;
 use validator_derive::Validate;
 
@@ -10,19 +13,36 @@ use super::domain::model::{UserLogin, UserRegistry, UserUpdate};
 use super::domain::service::DynUserService;
 use super::domain::user::AuthUser;
 
-#[derive(Serialize, Deserialize, Validate)]
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
+#[aliases(
+    UserRegisterBody = UserRequestDto<UserRegisterApiRequest>,
+    UserLoginBody = UserRequestDto<UserLoginRequest>,
+    UserUpdateBody = UserRequestDto<UserUpdateApiRequest>,
+)]
 pub struct UserRequestDto<T: Validate> {
     #[validate(nested)]
     user: T,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
+#[aliases(UserBody = UserResponseDto<UserResponse>)]
 pub struct UserResponseDto<T> {
     user: T,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/users",
+    tag = "user",
+    request_body = UserRegisterBody,
+    responses(
+        (status = 200, description = "Registered user", body = UserBody),
+        (status = 400, description = "Email or username is taken, or invalid", body = ErrorResponse),
+        (status = 422, description = "Missing or mistyped field", body = ErrorResponse),
+    )
+)]
 pub async fn register_api(
-    Extension(service): Extension<DynUserService>,
+    State(service): State<DynUserService>,
     ValidationExtractor(request): ValidationExtractor<UserRequestDto<UserRegisterApiRequest>>,
 ) -> Result<Json<UserResponseDto<UserResponse>>, AppError> {
     let user_registry = request.user;
@@ -40,8 +60,19 @@ pub async fn register_api(
     Ok(Json(UserResponseDto { user: response }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/users/login",
+    tag = "user",
+    request_body = UserLoginBody,
+    responses(
+        (status = 200, description = "Logged in user", body = UserBody),
+        (status = 400, description = "Wrong email or password", body = ErrorResponse),
+        (status = 422, description = "Missing or mistyped field", body = ErrorResponse),
+    )
+)]
 pub async fn login_api(
-    Extension(service): Extension<DynUserService>,
+    State(service): State<DynUserService>,
     ValidationExtractor(request): ValidationExtractor<UserRequestDto<UserLoginRequest>>,
 ) -> Result<Json<UserResponseDto<UserResponse>>, AppError> {
     let auth_user = service.login(request.user.to_login()).await?;
@@ -50,21 +81,52 @@ pub async fn login_api(
     Ok(Json(UserResponseDto { user: response }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/user",
+    tag = "user",
+    security(("token" = [])),
+    responses(
+        (status = 200, description = "Current user", body = UserBody,
+            headers(("ETag" = String, description = "Version of the user, send it back in `If-Match`"))),
+        (status = 401, description = "Missing token", body = ErrorResponse),
+        (status = 400, description = "Invalid or expired token", body = ErrorResponse),
+    )
+)]
 pub async fn get_info_api(
     JwtValidationExtractor(id): JwtValidationExtractor,
-    Extension(service): Extension<DynUserService>,
-) -> Result<Json<UserResponseDto<UserResponse>>, AppError> {
+    State(service): State<DynUserService>,
+) -> Result<(HeaderMap, Json<UserResponseDto<UserResponse>>), AppError> {
     let auth_user = service.get_info(id).await?;
 
+    let headers = version_headers(auth_user.version());
     let response = UserResponse::new(auth_user);
-    Ok(Json(UserResponseDto { user: response }))
+    Ok((headers, Json(UserResponseDto { user: response })))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/user",
+    tag = "user",
+    security(("token" = [])),
+    params(("If-Match" = Option<String>, Header, description = "`ETag` of the user as last read; the update is refused if it changed since")),
+    request_body = UserUpdateBody,
+    responses(
+        (status = 200, description = "Updated user", body = UserBody,
+            headers(("ETag" = String, description = "Version of the updated user"))),
+        (status = 400, description = "Email is taken, or invalid", body = ErrorResponse),
+        (status = 401, description = "Missing token", body = ErrorResponse),
+        (status = 412, description = "User changed since the `If-Match` version", body = ErrorResponse),
+        (status = 422, description = "Mistyped field", body = ErrorResponse),
+    )
+)]
 pub async fn update_user_api(
     JwtValidationExtractor(id): JwtValidationExtractor,
-    Extension(service): Extension<DynUserService>,
+    State(service): State<DynUserService>,
+    headers: HeaderMap,
     ValidationExtractor(request): ValidationExtractor<UserRequestDto<UserUpdateApiRequest>>,
-) -> Result<Json<UserResponseDto<UserResponse>>, AppError> {
+) -> Result<(HeaderMap, Json<UserResponseDto<UserResponse>>), AppError> {
+    let expected_version = if_match_version(&headers)?;
     let user_update_api_request = request.user;
     if user_update_api_request.email.clone().is_some() {
         service
@@ -72,15 +134,49 @@ pub async fn update_user_api(
             .await?;
     }
 
-    let auth_user = service
-        .update(id, user_update_api_request.to_update())
-        .await?;
+    let user_update = user_update_api_request
+        .to_update()
+        .expect_version(expected_version);
+    let auth_user = service.update(id, user_update).await?;
 
+    let headers = version_headers(auth_user.version());
     let response = UserResponse::new(auth_user);
-    Ok(Json(UserResponseDto { user: response }))
+    Ok((headers, Json(UserResponseDto { user: response })))
+}
+
+/// The user's version as a strong `ETag`, the only kind `If-Match` compares.
+fn version_headers(version: i64) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::ETAG,
+        HeaderValue::try_from(format!("\"{version}\"")).unwrap(),
+    );
+    headers
+}
+
+/// Version named by `If-Match`, or `None` when the header is absent or `*`. A tag
+/// that is weak or not one of ours can never match, so the update is refused.
+fn if_match_version(headers: &HeaderMap) -> Result<Option<i64>, AppError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| AppError::PreconditionFailed)?
+        .trim();
+    if value == "*" {
+        return Ok(None);
+    }
+
+    value
+        .strip_prefix('"')
+        .and_then(|tag| tag.strip_suffix('"'))
+        .and_then(|version| version.parse().ok())
+        .map(Some)
+        .ok_or(AppError::PreconditionFailed)
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, ToSchema)]
 pub struct UserRegisterApiRequest {
     #[validate(length(min = 1, message = "User Name is required."))]
     username: String,
@@ -95,7 +191,7 @@ impl UserRegisterApiRequest {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct UserResponse {
     email: String,
     token: String,
@@ -116,7 +212,7 @@ impl UserResponse {
     }
 }
 
-#[derive(Validate, Deserialize)]
+#[derive(Validate, Deserialize, ToSchema)]
 pub struct UserLoginRequest {
     #[validate(length(min = 1, message = "Email is required."))]
     email: String,
@@ -130,7 +226,7 @@ impl UserLoginRequest {
     }
 }
 
-#[derive(Deserialize, Validate)]
+#[derive(Deserialize, Validate, ToSchema)]
 pub struct UserUpdateApiRequest {
     #[validate(length(min = 1, message = "User Name is required."))]
     username: Option<String>,
@@ -0,0 +1,194 @@
+#![allow(dead_code)]
+
+use axum::body::{to_bytes, Body};
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use axum::Router;
+use real_world_axum_sqlx::auth::clock::DynClock;
+use real_world_axum_sqlx::config::container::{AppContainer, AppContainerBuilder};
+use real_world_axum_sqlx::config::db::{DbPool, PgPool};
+use real_world_axum_sqlx::config::settings::Settings;
+use real_world_axum_sqlx::create_route;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+pub const PASSWORD: &str = "password1234";
+
+pub const TEST_SETTINGS: &str = r#"
+[database]
+url = "postgres://unused/realworld"
+
+[jwt]
+secret_key = "integration-test-secret"
+
+[rate_limit]
+enabled = false
+"#;
+
+/// The application router on top of the per-test database created by `#[sqlx::test]`.
+pub struct TestApp {
+    router: Router,
+    auth_scheme: &'static str,
+    pub pool: PgPool,
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+impl TestApp {
+    pub async fn new(pool: PgPool) -> Self {
+        Self::with_settings(pool, TEST_SETTINGS).await
+    }
+
+    pub async fn with_settings(pool: PgPool, toml: &str) -> Self {
+        Self::with_container(pool, toml, |builder| builder.build()).await
+    }
+
+    /// Runs the application on `clock` instead of the system clock.
+    pub async fn with_clock(pool: PgPool, clock: DynClock) -> Self {
+        Self::with_container(pool, TEST_SETTINGS, |builder| builder.clock(clock).build()).await
+    }
+
+    /// Lets the test swap components of the container before building it and the router.
+    pub async fn with_container(
+        pool: PgPool,
+        toml: &str,
+        build: impl FnOnce(AppContainerBuilder) -> AppContainer,
+    ) -> Self {
+        let settings = Settings::from_toml(toml).expect("valid test settings");
+        let container = build(AppContainer::builder(settings, DbPool::new(pool.clone())));
+        Self::from_container(pool, container)
+    }
+
+    /// Routes read-only queries to `replica`, as `database.replica_url` does.
+    pub async fn with_replica(pool: PgPool, replica: PgPool) -> Self {
+        let settings = Settings::from_toml(TEST_SETTINGS).expect("valid test settings");
+        let db_pool = DbPool::new(pool.clone()).with_replica(replica, true);
+        Self::from_container(pool, AppContainer::builder(settings, db_pool).build())
+    }
+
+    fn from_container(pool: PgPool, container: AppContainer) -> Self {
+        Self {
+            router: create_route(container),
+            auth_scheme: "Bearer",
+            pool,
+        }
+    }
+
+    /// Sends tokens as `Authorization: <scheme> <token>`; the RealWorld spec uses `Token`.
+    pub fn with_auth_scheme(self, auth_scheme: &'static str) -> Self {
+        Self {
+            auth_scheme,
+            ..self
+        }
+    }
+
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> TestResponse {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            builder = builder.header(
+                header::AUTHORIZATION,
+                format!("{} {token}", self.auth_scheme),
+            );
+        }
+        let request = match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        }
+        .unwrap();
+
+        self.send(request).await
+    }
+
+    /// Sends a request built by the test, for headers the helpers do not set.
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = if bytes.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_slice(&bytes)
+                .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()))
+        };
+
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub async fn get(&self, uri: &str, token: Option<&str>) -> TestResponse {
+        self.request(Method::GET, uri, token, None).await
+    }
+
+    pub async fn post(&self, uri: &str, token: Option<&str>, body: Value) -> TestResponse {
+        self.request(Method::POST, uri, token, Some(body)).await
+    }
+
+    pub async fn post_empty(&self, uri: &str, token: Option<&str>) -> TestResponse {
+        self.request(Method::POST, uri, token, None).await
+    }
+
+    pub async fn put(&self, uri: &str, token: Option<&str>, body: Value) -> TestResponse {
+        self.request(Method::PUT, uri, token, Some(body)).await
+    }
+
+    pub async fn delete(&self, uri: &str, token: Option<&str>) -> TestResponse {
+        self.request(Method::DELETE, uri, token, None).await
+    }
+
+    /// Registers `username` with `username@example.com` and [`PASSWORD`], returning its token.
+    pub async fn register(&self, username: &str) -> String {
+        let response = self
+            .post(
+                "/api/users",
+                None,
+                json!({
+                    "user": {
+                        "username": username,
+                        "email": email_of(username),
+                        "password": PASSWORD,
+                    }
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        token_of(&response)
+    }
+
+    pub async fn login(&self, username: &str) -> String {
+        let response = self
+            .post(
+                "/api/users/login",
+                None,
+                json!({ "user": { "email": email_of(username), "password": PASSWORD } }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+        token_of(&response)
+    }
+}
+
+pub fn email_of(username: &str) -> String {
+    format!("{username}@example.com")
+}
+
+pub fn token_of(response: &TestResponse) -> String {
+    response.body["user"]["token"]
+        .as_str()
+        .expect("response carries a token")
+        .to_owned()
+}
@@ -0,0 +1,486 @@
+//! Scenarios of the RealWorld API spec collection, asserting the response shapes
+//! field by field: camelCase names, and `null` (never missing) for empty optionals.
+//!
+//! Article, comment, favorite, tag and feed endpoints are not implemented yet; their
+//! scenarios are ported but ignored until the routes exist.
+
+mod common;
+
+use axum::http::StatusCode;
+use chrono::DateTime;
+use common::{email_of, token_of, TestApp, PASSWORD};
+use real_world_axum_sqlx::config::db::PgPool;
+use serde_json::{json, Value};
+
+async fn spec_app(pool: PgPool) -> TestApp {
+    TestApp::new(pool).await.with_auth_scheme("Token")
+}
+
+fn assert_keys(value: &Value, expected: &[&str]) {
+    let object = value
+        .as_object()
+        .unwrap_or_else(|| panic!("expected an object, got {value}"));
+    let mut keys: Vec<&str> = object.keys().map(String::as_str).collect();
+    let mut expected = expected.to_vec();
+    keys.sort_unstable();
+    expected.sort_unstable();
+    assert_eq!(keys, expected, "keys of {value}");
+}
+
+fn assert_string_or_null(value: &Value) {
+    assert!(value.is_string() || value.is_null(), "{value}");
+}
+
+fn assert_iso8601(value: &Value) {
+    let text = value.as_str().unwrap_or_else(|| panic!("{value}"));
+    assert!(DateTime::parse_from_rfc3339(text).is_ok(), "{text}");
+}
+
+fn assert_user(user: &Value) {
+    assert_keys(user, &["email", "token", "username", "bio", "image"]);
+    assert!(user["email"].is_string());
+    assert!(user["token"].is_string());
+    assert!(user["username"].is_string());
+    assert_string_or_null(&user["bio"]);
+    assert_string_or_null(&user["image"]);
+}
+
+fn assert_profile(profile: &Value) {
+    assert_keys(profile, &["username", "bio", "image", "following"]);
+    assert!(profile["username"].is_string());
+    assert_string_or_null(&profile["bio"]);
+    assert_string_or_null(&profile["image"]);
+    assert!(profile["following"].is_boolean());
+}
+
+fn assert_article(article: &Value) {
+    assert_keys(
+        article,
+        &[
+            "slug",
+            "title",
+            "description",
+            "body",
+            "tagList",
+            "createdAt",
+            "updatedAt",
+            "favorited",
+            "favoritesCount",
+            "author",
+        ],
+    );
+    assert!(article["slug"].is_string());
+    assert!(article["title"].is_string());
+    assert!(article["description"].is_string());
+    assert!(article["body"].is_string());
+    assert!(article["tagList"]
+        .as_array()
+        .is_some_and(|tags| tags.iter().all(Value::is_string)));
+    assert_iso8601(&article["createdAt"]);
+    assert_iso8601(&article["updatedAt"]);
+    assert!(article["favorited"].is_boolean());
+    assert!(article["favoritesCount"].is_u64());
+    assert_profile(&article["author"]);
+}
+
+fn assert_articles(body: &Value) {
+    assert_keys(body, &["articles", "articlesCount"]);
+    let articles = body["articles"].as_array().expect("articles array");
+    articles.iter().for_each(assert_article);
+    assert!(body["articlesCount"].is_u64());
+}
+
+fn assert_comment(comment: &Value) {
+    assert_keys(comment, &["id", "createdAt", "updatedAt", "body", "author"]);
+    assert!(comment["id"].is_i64());
+    assert_iso8601(&comment["createdAt"]);
+    assert_iso8601(&comment["updatedAt"]);
+    assert!(comment["body"].is_string());
+    assert_profile(&comment["author"]);
+}
+
+async fn create_article(app: &TestApp, token: &str) -> Value {
+    let response = app
+        .post(
+            "/api/articles",
+            Some(token),
+            json!({
+                "article": {
+                    "title": "How to train your dragon",
+                    "description": "Ever wonder how?",
+                    "body": "Very carefully.",
+                    "tagList": ["training", "dragons"]
+                }
+            }),
+        )
+        .await;
+    assert!(response.status.is_success(), "{}", response.body);
+    response.body["article"].clone()
+}
+
+mod auth {
+    use super::*;
+
+    #[sqlx::test]
+    async fn register(pool: PgPool) {
+        let app = spec_app(pool).await;
+
+        let response = app
+            .post(
+                "/api/users",
+                None,
+                json!({ "user": { "email": email_of("jake"), "password": PASSWORD, "username": "jake" } }),
+            )
+            .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_keys(&response.body, &["user"]);
+        let user = &response.body["user"];
+        assert_user(user);
+        assert_eq!(user["email"], email_of("jake"));
+        assert_eq!(user["username"], "jake");
+        assert!(user["bio"].is_null());
+        assert!(user["image"].is_null());
+    }
+
+    #[sqlx::test]
+    async fn login(pool: PgPool) {
+        let app = spec_app(pool).await;
+        app.register("jake").await;
+
+        let response = app
+            .post(
+                "/api/users/login",
+                None,
+                json!({ "user": { "email": email_of("jake"), "password": PASSWORD } }),
+            )
+            .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_keys(&response.body, &["user"]);
+        assert_user(&response.body["user"]);
+        assert_eq!(response.body["user"]["email"], email_of("jake"));
+    }
+
+    #[sqlx::test]
+    async fn login_and_remember_token(pool: PgPool) {
+        let app = spec_app(pool).await;
+        app.register("jake").await;
+
+        let login = app
+            .post(
+                "/api/users/login",
+                None,
+                json!({ "user": { "email": email_of("jake"), "password": PASSWORD } }),
+            )
+            .await;
+        let token = token_of(&login);
+        let current = app.get("/api/user", Some(&token)).await;
+
+        assert_eq!(current.status, StatusCode::OK);
+        assert_user(&current.body["user"]);
+        assert_eq!(current.body["user"]["username"], "jake");
+    }
+
+    #[sqlx::test]
+    async fn current_user(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let token = app.register("jake").await;
+
+        let response = app.get("/api/user", Some(&token)).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_keys(&response.body, &["user"]);
+        assert_user(&response.body["user"]);
+        assert_eq!(response.body["user"]["email"], email_of("jake"));
+    }
+
+    #[sqlx::test]
+    async fn update_user(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let token = app.register("jake").await;
+
+        let response = app
+            .put(
+                "/api/user",
+                Some(&token),
+                json!({ "user": { "email": email_of("jake.updated") } }),
+            )
+            .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_keys(&response.body, &["user"]);
+        assert_user(&response.body["user"]);
+        assert_eq!(response.body["user"]["email"], email_of("jake.updated"));
+    }
+}
+
+mod profiles {
+    use super::*;
+
+    #[sqlx::test]
+    async fn profile(pool: PgPool) {
+        let app = spec_app(pool).await;
+        app.register("celeb_jake").await;
+        let token = app.register("jake").await;
+
+        let response = app.get("/api/profiles/celeb_jake", Some(&token)).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_keys(&response.body, &["profile"]);
+        let profile = &response.body["profile"];
+        assert_profile(profile);
+        assert_eq!(profile["username"], "celeb_jake");
+        assert_eq!(profile["following"], false);
+    }
+
+    #[sqlx::test]
+    async fn follow_profile(pool: PgPool) {
+        let app = spec_app(pool).await;
+        app.register("celeb_jake").await;
+        let token = app.register("jake").await;
+
+        let response = app
+            .post(
+                "/api/profiles/celeb_jake/follow",
+                Some(&token),
+                json!({ "user": { "email": email_of("jake") } }),
+            )
+            .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_keys(&response.body, &["profile"]);
+        assert_profile(&response.body["profile"]);
+        assert_eq!(response.body["profile"]["following"], true);
+    }
+
+    #[sqlx::test]
+    async fn unfollow_profile(pool: PgPool) {
+        let app = spec_app(pool).await;
+        app.register("celeb_jake").await;
+        let token = app.register("jake").await;
+        app.post_empty("/api/profiles/celeb_jake/follow", Some(&token))
+            .await;
+
+        let response = app
+            .delete("/api/profiles/celeb_jake/follow", Some(&token))
+            .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_keys(&response.body, &["profile"]);
+        assert_profile(&response.body["profile"]);
+        assert_eq!(response.body["profile"]["following"], false);
+    }
+}
+
+mod articles {
+    use super::*;
+
+    #[sqlx::test]
+    #[ignore = "article endpoints are not implemented yet"]
+    async fn all_articles(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let token = app.register("jake").await;
+        create_article(&app, &token).await;
+
+        let response = app.get("/api/articles", None).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_articles(&response.body);
+        assert_eq!(response.body["articlesCount"], 1);
+    }
+
+    #[sqlx::test]
+    #[ignore = "article endpoints are not implemented yet"]
+    async fn create_article_returns_article(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let token = app.register("jake").await;
+
+        let article = create_article(&app, &token).await;
+
+        assert_article(&article);
+        assert_eq!(article["title"], "How to train your dragon");
+        assert_eq!(article["description"], "Ever wonder how?");
+        assert_eq!(article["body"], "Very carefully.");
+        assert_eq!(article["tagList"], json!(["dragons", "training"]));
+        assert_eq!(article["favorited"], false);
+        assert_eq!(article["favoritesCount"], 0);
+        assert_eq!(article["author"]["username"], "jake");
+    }
+
+    #[sqlx::test]
+    #[ignore = "article endpoints are not implemented yet"]
+    async fn single_article_by_slug(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let token = app.register("jake").await;
+        let slug = create_article(&app, &token).await["slug"].clone();
+
+        let response = app
+            .get(&format!("/api/articles/{}", slug.as_str().unwrap()), None)
+            .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_keys(&response.body, &["article"]);
+        assert_article(&response.body["article"]);
+        assert_eq!(response.body["article"]["slug"], slug);
+    }
+
+    #[sqlx::test]
+    #[ignore = "article endpoints are not implemented yet"]
+    async fn articles_by_author_and_tag(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let token = app.register("jake").await;
+        create_article(&app, &token).await;
+
+        let by_author = app.get("/api/articles?author=jake", None).await;
+        let by_tag = app.get("/api/articles?tag=dragons", None).await;
+        let by_other_tag = app.get("/api/articles?tag=unknown", None).await;
+
+        assert_articles(&by_author.body);
+        assert_eq!(by_author.body["articlesCount"], 1);
+        assert_articles(&by_tag.body);
+        assert_eq!(by_tag.body["articlesCount"], 1);
+        assert_eq!(by_other_tag.body["articlesCount"], 0);
+    }
+
+    #[sqlx::test]
+    #[ignore = "article endpoints are not implemented yet"]
+    async fn update_article(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let token = app.register("jake").await;
+        let slug = create_article(&app, &token).await["slug"].clone();
+
+        let response = app
+            .put(
+                &format!("/api/articles/{}", slug.as_str().unwrap()),
+                Some(&token),
+                json!({ "article": { "body": "With two hands" } }),
+            )
+            .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_article(&response.body["article"]);
+        assert_eq!(response.body["article"]["body"], "With two hands");
+    }
+
+    #[sqlx::test]
+    #[ignore = "article endpoints are not implemented yet"]
+    async fn delete_article(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let token = app.register("jake").await;
+        let slug = create_article(&app, &token).await["slug"].clone();
+        let uri = format!("/api/articles/{}", slug.as_str().unwrap());
+
+        let response = app.delete(&uri, Some(&token)).await;
+
+        assert!(response.status.is_success());
+        assert_eq!(app.get(&uri, None).await.status, StatusCode::NOT_FOUND);
+    }
+}
+
+mod favorites {
+    use super::*;
+
+    #[sqlx::test]
+    #[ignore = "article endpoints are not implemented yet"]
+    async fn favorite_and_unfavorite_article(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let author = app.register("jake").await;
+        let reader = app.register("jane").await;
+        let slug = create_article(&app, &author).await["slug"].clone();
+        let uri = format!("/api/articles/{}/favorite", slug.as_str().unwrap());
+
+        let favorited = app.post_empty(&uri, Some(&reader)).await;
+        let by_user = app.get("/api/articles?favorited=jane", None).await;
+        let unfavorited = app.delete(&uri, Some(&reader)).await;
+
+        assert_article(&favorited.body["article"]);
+        assert_eq!(favorited.body["article"]["favorited"], true);
+        assert_eq!(favorited.body["article"]["favoritesCount"], 1);
+        assert_articles(&by_user.body);
+        assert_eq!(by_user.body["articlesCount"], 1);
+        assert_article(&unfavorited.body["article"]);
+        assert_eq!(unfavorited.body["article"]["favorited"], false);
+        assert_eq!(unfavorited.body["article"]["favoritesCount"], 0);
+    }
+}
+
+mod comments {
+    use super::*;
+
+    #[sqlx::test]
+    #[ignore = "article endpoints are not implemented yet"]
+    async fn create_list_and_delete_comment(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let token = app.register("jake").await;
+        let slug = create_article(&app, &token).await["slug"].clone();
+        let uri = format!("/api/articles/{}/comments", slug.as_str().unwrap());
+
+        let created = app
+            .post(
+                &uri,
+                Some(&token),
+                json!({ "comment": { "body": "Thank you so much!" } }),
+            )
+            .await;
+        assert_keys(&created.body, &["comment"]);
+        let comment = &created.body["comment"];
+        assert_comment(comment);
+        assert_eq!(comment["body"], "Thank you so much!");
+
+        let listed = app.get(&uri, None).await;
+        assert_keys(&listed.body, &["comments"]);
+        let comments = listed.body["comments"].as_array().unwrap();
+        assert_eq!(comments.len(), 1);
+        comments.iter().for_each(assert_comment);
+
+        let deleted = app
+            .delete(&format!("{uri}/{}", comment["id"]), Some(&token))
+            .await;
+        assert!(deleted.status.is_success());
+        let listed = app.get(&uri, None).await;
+        assert_eq!(listed.body["comments"], json!([]));
+    }
+}
+
+mod tags {
+    use super::*;
+
+    #[sqlx::test]
+    #[ignore = "article endpoints are not implemented yet"]
+    async fn all_tags(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let token = app.register("jake").await;
+        create_article(&app, &token).await;
+
+        let response = app.get("/api/tags", None).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_keys(&response.body, &["tags"]);
+        let tags = response.body["tags"].as_array().unwrap();
+        assert!(tags.contains(&json!("dragons")));
+        assert!(tags.contains(&json!("training")));
+    }
+}
+
+mod feed {
+    use super::*;
+
+    #[sqlx::test]
+    #[ignore = "article endpoints are not implemented yet"]
+    async fn feed_lists_followed_authors(pool: PgPool) {
+        let app = spec_app(pool).await;
+        let author = app.register("celeb_jake").await;
+        let reader = app.register("jake").await;
+        create_article(&app, &author).await;
+        app.post_empty("/api/profiles/celeb_jake/follow", Some(&reader))
+            .await;
+
+        let response = app.get("/api/articles/feed", Some(&reader)).await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_articles(&response.body);
+        assert_eq!(response.body["articlesCount"], 1);
+        assert_eq!(response.body["articles"][0]["author"]["following"], true);
+    }
+}
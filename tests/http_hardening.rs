@@ -0,0 +1,244 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use common::{email_of, TestApp, PASSWORD, TEST_SETTINGS};
+use real_world_axum_sqlx::config::db::PgPool;
+use serde_json::json;
+
+const FRONTEND: &str = "http://localhost:3000";
+
+fn cors_settings() -> String {
+    format!(
+        r#"{TEST_SETTINGS}
+[cors]
+allowed_origins = ["{FRONTEND}"]
+allow_credentials = true
+"#
+    )
+}
+
+fn preflight(origin: &str) -> Request<Body> {
+    Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/api/user")
+        .header(header::ORIGIN, origin)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+        .header(
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            "authorization,if-match,if-none-match,x-request-id",
+        )
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[sqlx::test]
+async fn cors_allows_configured_origin_only(pool: PgPool) {
+    let app = TestApp::with_settings(pool, &cors_settings()).await;
+
+    let allowed = app.send(preflight(FRONTEND)).await;
+    let other = app.send(preflight("https://evil.example")).await;
+
+    assert_eq!(allowed.status, StatusCode::OK);
+    assert_eq!(
+        allowed.headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+        FRONTEND
+    );
+    assert_eq!(
+        allowed.headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS],
+        "true"
+    );
+    assert!(allowed.headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+        .to_str()
+        .unwrap()
+        .contains("PUT"));
+    let allowed_headers = allowed.headers[header::ACCESS_CONTROL_ALLOW_HEADERS]
+        .to_str()
+        .unwrap();
+    for name in ["if-match", "if-none-match", "x-request-id"] {
+        assert!(allowed_headers.contains(name), "{name} is not allowed");
+    }
+    assert!(!other
+        .headers
+        .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
+
+#[sqlx::test]
+async fn cors_exposes_etag_and_rate_limit_headers(pool: PgPool) {
+    let app = TestApp::with_settings(pool, &cors_settings()).await;
+    let request = Request::get("/health/live")
+        .header(header::ORIGIN, FRONTEND)
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.send(request).await;
+
+    let exposed = response.headers[header::ACCESS_CONTROL_EXPOSE_HEADERS]
+        .to_str()
+        .unwrap();
+    for name in [
+        "x-ratelimit-limit",
+        "x-ratelimit-remaining",
+        "x-ratelimit-reset",
+        "retry-after",
+        "etag",
+        "x-request-id",
+    ] {
+        assert!(exposed.contains(name), "{name} is not exposed");
+    }
+}
+
+#[sqlx::test]
+async fn cors_is_off_without_origins(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let response = app.send(preflight(FRONTEND)).await;
+
+    assert!(!response
+        .headers
+        .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+}
+
+#[sqlx::test]
+async fn responses_carry_security_headers(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let ok = app.get("/health/live", None).await;
+    let error = app.get("/api/user", None).await;
+
+    for response in [ok, error] {
+        assert_eq!(response.headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        assert_eq!(response.headers[header::X_FRAME_OPTIONS], "DENY");
+        assert_eq!(response.headers[header::REFERRER_POLICY], "no-referrer");
+        assert!(response
+            .headers
+            .contains_key(header::CONTENT_SECURITY_POLICY));
+    }
+}
+
+#[sqlx::test]
+async fn oversized_body_is_rejected_as_app_error(pool: PgPool) {
+    let app = TestApp::with_settings(
+        pool,
+        &TEST_SETTINGS.replace(
+            "[database]",
+            "[server]\nrequest_body_limit_bytes = 256\n\n[database]",
+        ),
+    )
+    .await;
+    let body = json!({
+        "user": { "username": "jake", "email": email_of("jake"), "password": PASSWORD, "bio": "x".repeat(512) }
+    })
+    .to_string();
+
+    let declared = app
+        .send(
+            Request::post("/api/users")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(header::CONTENT_LENGTH, body.len())
+                .body(Body::from(body.clone()))
+                .unwrap(),
+        )
+        .await;
+    let streamed = app
+        .send(
+            Request::post("/api/users")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await;
+
+    for response in [declared, streamed] {
+        assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(response.body["code"], 41300);
+    }
+}
+
+#[sqlx::test]
+async fn login_is_rate_limited_per_client(pool: PgPool) {
+    let app = TestApp::with_settings(
+        pool,
+        &TEST_SETTINGS.replace(
+            "enabled = false",
+            "enabled = true\ntrust_forwarded_for = true\nauth = { capacity = 2, refill_per_minute = 1 }",
+        ),
+    )
+    .await;
+    let login = |client: &str| {
+        Request::post("/api/users/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("x-forwarded-for", client)
+            .body(Body::from(
+                json!({ "user": { "email": email_of("jake"), "password": PASSWORD } }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    let first = app.send(login("10.0.0.1")).await;
+    app.send(login("10.0.0.1")).await;
+    let limited = app.send(login("10.0.0.1")).await;
+    let other_client = app.send(login("10.0.0.2")).await;
+
+    assert_ne!(first.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(first.headers["x-ratelimit-limit"], "2");
+    assert_eq!(limited.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(limited.body["code"], 42900);
+    assert_eq!(limited.headers[header::RETRY_AFTER], "60");
+    assert_ne!(other_client.status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[sqlx::test]
+async fn realworld_format_keeps_rate_limit_headers(pool: PgPool) {
+    let app = TestApp::with_settings(
+        pool,
+        &TEST_SETTINGS.replace(
+            "enabled = false",
+            "enabled = true\ntrust_forwarded_for = true\nauth = { capacity = 1, refill_per_minute = 1 }\n\n[server]\nerror_format = \"realworld\"",
+        ),
+    )
+    .await;
+    let login = || {
+        Request::post("/api/users/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("x-forwarded-for", "10.0.0.1")
+            .body(Body::from(
+                json!({ "user": { "email": email_of("jake"), "password": PASSWORD } }).to_string(),
+            ))
+            .unwrap()
+    };
+
+    app.send(login()).await;
+    let limited = app.send(login()).await;
+
+    assert_eq!(limited.status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(limited.body["errors"].is_object());
+    assert_eq!(limited.headers[header::RETRY_AFTER], "60");
+    assert_eq!(limited.headers["x-ratelimit-limit"], "1");
+    assert_eq!(limited.headers["x-ratelimit-remaining"], "0");
+    assert!(limited.headers.contains_key("x-ratelimit-reset"));
+    assert_eq!(limited.headers[header::CONTENT_TYPE], "application/json");
+}
+
+#[sqlx::test]
+async fn responses_are_compressed_when_accepted(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("celeb").await;
+    let get_profile = |encoding: &str| {
+        Request::get("/api/profiles/celeb")
+            .header(header::ACCEPT_ENCODING, encoding)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let gzip = app.send(get_profile("gzip")).await;
+    let brotli = app.send(get_profile("br;q=1.0, gzip;q=0.5")).await;
+    let zstd = app.send(get_profile("zstd")).await;
+    let identity = app.send(get_profile("identity")).await;
+
+    assert_eq!(gzip.headers[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(brotli.headers[header::CONTENT_ENCODING], "br");
+    assert_eq!(zstd.headers[header::CONTENT_ENCODING], "zstd");
+    assert!(!identity.headers.contains_key(header::CONTENT_ENCODING));
+    assert_eq!(identity.body["profile"]["username"], "celeb");
+}
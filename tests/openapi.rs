@@ -0,0 +1,66 @@
+mod common;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use axum::http::StatusCode;
+use common::{TestApp, TEST_SETTINGS};
+use real_world_axum_sqlx::config::db::PgPool;
+use real_world_axum_sqlx::openapi::api_doc::ApiDoc;
+use utoipa::OpenApi;
+
+const COMMITTED_SPEC: &str = "docs/openapi.json";
+
+/// Run with `UPDATE_OPENAPI=1` to rewrite the committed spec after changing the API.
+#[test]
+fn committed_spec_matches_code() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(COMMITTED_SPEC);
+    let generated = ApiDoc::openapi().to_pretty_json().unwrap() + "\n";
+
+    if env::var_os("UPDATE_OPENAPI").is_some() {
+        fs::write(&path, generated).unwrap();
+        return;
+    }
+
+    let committed = fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "{COMMITTED_SPEC} is out of date, run `UPDATE_OPENAPI=1 cargo test --test openapi`"
+    );
+}
+
+#[sqlx::test]
+async fn serves_generated_spec(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let response = app.get("/api/openapi.json", None).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(
+        response.body,
+        serde_json::to_value(ApiDoc::openapi()).unwrap()
+    );
+    assert!(response.body["paths"]["/api/users"]["post"].is_object());
+    assert!(response.body["components"]["securitySchemes"]["token"].is_object());
+}
+
+#[sqlx::test]
+async fn swagger_ui_is_opt_in(pool: PgPool) {
+    let disabled = TestApp::new(pool.clone()).await;
+    let enabled = TestApp::with_settings(
+        pool,
+        &TEST_SETTINGS.replace("[database]", "[server]\nswagger_ui = true\n\n[database]"),
+    )
+    .await;
+
+    let not_found = disabled.get("/api/docs", None).await;
+    let page = enabled.get("/api/docs", None).await;
+
+    assert_eq!(not_found.status, StatusCode::NOT_FOUND);
+    assert_eq!(page.status, StatusCode::OK);
+    assert!(page
+        .body
+        .as_str()
+        .is_some_and(|html| html.contains("/api/openapi.json")));
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use common::TestApp;
+use real_world_axum_sqlx::config::db::PgPool;
+
+#[sqlx::test]
+async fn get_profile_without_token_is_not_following(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("celeb").await;
+
+    let response = app.get("/api/profiles/celeb", None).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    let profile = &response.body["profile"];
+    assert_eq!(profile["username"], "celeb");
+    assert_eq!(profile["following"], false);
+    assert!(profile["bio"].is_null());
+    assert!(profile["image"].is_null());
+}
+
+#[sqlx::test]
+async fn get_unknown_profile_fails(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let response = app.get("/api/profiles/nobody", None).await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn follow_user_marks_profile_as_followed(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("celeb").await;
+    let token = app.register("fan").await;
+
+    let followed = app
+        .post_empty("/api/profiles/celeb/follow", Some(&token))
+        .await;
+
+    assert_eq!(followed.status, StatusCode::OK);
+    assert_eq!(followed.body["profile"]["following"], true);
+
+    let as_fan = app.get("/api/profiles/celeb", Some(&token)).await;
+    let anonymous = app.get("/api/profiles/celeb", None).await;
+    assert_eq!(as_fan.body["profile"]["following"], true);
+    assert_eq!(anonymous.body["profile"]["following"], false);
+}
+
+#[sqlx::test]
+async fn follow_user_twice_keeps_following(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("celeb").await;
+    let token = app.register("fan").await;
+
+    for _ in 0..2 {
+        let response = app
+            .post_empty("/api/profiles/celeb/follow", Some(&token))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["profile"]["following"], true);
+    }
+}
+
+#[sqlx::test]
+async fn follow_user_requires_token(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("celeb").await;
+
+    let response = app.post_empty("/api/profiles/celeb/follow", None).await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn unfollow_user_clears_following(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("celeb").await;
+    let token = app.register("fan").await;
+    app.post_empty("/api/profiles/celeb/follow", Some(&token))
+        .await;
+
+    let response = app
+        .delete("/api/profiles/celeb/unfollow", Some(&token))
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["profile"]["following"], false);
+    let profile = app.get("/api/profiles/celeb", Some(&token)).await;
+    assert_eq!(profile.body["profile"]["following"], false);
+}
+
+#[sqlx::test]
+async fn unfollow_user_requires_token(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("celeb").await;
+
+    let response = app.delete("/api/profiles/celeb/unfollow", None).await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn get_profile_with_current_etag_is_not_modified(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("celeb").await;
+    let token = app.register("fan").await;
+    let conditional = |etag: &str| {
+        Request::get("/api/profiles/celeb")
+            .header(header::AUTHORIZATION, format!("Token {token}"))
+            .header(header::IF_NONE_MATCH, etag)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let first = app.get("/api/profiles/celeb", Some(&token)).await;
+    let etag = first.headers[header::ETAG].to_str().unwrap().to_owned();
+    let unchanged = app.send(conditional(&etag)).await;
+    app.post_empty("/api/profiles/celeb/follow", Some(&token))
+        .await;
+    let changed = app.send(conditional(&etag)).await;
+
+    assert!(etag.starts_with("W/"));
+    assert_eq!(unchanged.status, StatusCode::NOT_MODIFIED);
+    assert!(unchanged.body.is_null());
+    assert_eq!(unchanged.headers[header::ETAG], etag.as_str());
+    assert_eq!(first.headers[header::VARY], "authorization");
+    assert_eq!(unchanged.headers[header::VARY], "authorization");
+    assert_eq!(changed.status, StatusCode::OK);
+    assert_eq!(changed.body["profile"]["following"], true);
+    assert_ne!(changed.headers[header::ETAG], etag.as_str());
+}
+
+#[sqlx::test]
+async fn concurrent_follows_insert_one_row(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("celeb").await;
+    let token = app.register("fan").await;
+
+    let follow = || app.post_empty("/api/profiles/celeb/follow", Some(&token));
+    let (first, second, third) = tokio::join!(follow(), follow(), follow());
+
+    for response in [first, second, third] {
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["profile"]["following"], true);
+    }
+    let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM user_follow WHERE deleted = false")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(rows, 1);
+}
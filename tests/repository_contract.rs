@@ -0,0 +1,449 @@
+//! Behaviour every `UserRepository` / `ProfileRepository` / `UnitOfWork`
+//! implementation must share.
+//! Each case runs once against the in-memory repositories and once against Postgres.
+
+use std::sync::Arc;
+
+use axum::async_trait;
+use real_world_axum_sqlx::auth::clock::RealClock;
+use real_world_axum_sqlx::config::db::{DbPool, PgPool};
+use real_world_axum_sqlx::config::error::AppError;
+use real_world_axum_sqlx::profile::domain::repository::ProfileRepository;
+use real_world_axum_sqlx::profile::repository::memory::InMemoryProfileRepository;
+use real_world_axum_sqlx::profile::repository::repository::ConcreteProfileRepository;
+use real_world_axum_sqlx::unit_of_work::memory::InMemoryUnitOfWork;
+use real_world_axum_sqlx::unit_of_work::postgres::ConcreteUnitOfWork;
+use real_world_axum_sqlx::unit_of_work::UnitOfWork;
+use real_world_axum_sqlx::user::domain::model::{UserRegistry, UserUpdate};
+use real_world_axum_sqlx::user::domain::repository::UserRepository;
+use real_world_axum_sqlx::user::domain::user::User;
+use real_world_axum_sqlx::user::repository::memory::InMemoryUserRepository;
+use real_world_axum_sqlx::user::repository::repository::ConcreteUserRepository;
+
+#[async_trait]
+trait Backend: Sync {
+    fn users(&self) -> &(dyn UserRepository + Send + Sync);
+    fn profiles(&self) -> &(dyn ProfileRepository + Send + Sync);
+    fn unit_of_work(&self) -> &(dyn UnitOfWork + Send + Sync);
+    async fn soft_delete_user(&self, id: i64);
+}
+
+struct MemoryBackend {
+    users: Arc<InMemoryUserRepository>,
+    profiles: Arc<InMemoryProfileRepository>,
+    unit_of_work: InMemoryUnitOfWork,
+}
+
+impl MemoryBackend {
+    fn new() -> Self {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let profiles = Arc::new(InMemoryProfileRepository::new());
+        Self {
+            unit_of_work: InMemoryUnitOfWork::new(users.clone(), profiles.clone()),
+            users,
+            profiles,
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for MemoryBackend {
+    fn users(&self) -> &(dyn UserRepository + Send + Sync) {
+        self.users.as_ref()
+    }
+
+    fn profiles(&self) -> &(dyn ProfileRepository + Send + Sync) {
+        self.profiles.as_ref()
+    }
+
+    fn unit_of_work(&self) -> &(dyn UnitOfWork + Send + Sync) {
+        &self.unit_of_work
+    }
+
+    async fn soft_delete_user(&self, id: i64) {
+        self.users.soft_delete(id);
+    }
+}
+
+struct PostgresBackend {
+    pool: PgPool,
+    users: ConcreteUserRepository,
+    profiles: ConcreteProfileRepository,
+    unit_of_work: ConcreteUnitOfWork,
+}
+
+impl PostgresBackend {
+    fn new(pool: PgPool) -> Self {
+        Self {
+            users: ConcreteUserRepository::new(DbPool::new(pool.clone()), Arc::new(RealClock)),
+            profiles: ConcreteProfileRepository::new(
+                DbPool::new(pool.clone()),
+                Arc::new(RealClock),
+            ),
+            unit_of_work: ConcreteUnitOfWork::new(DbPool::new(pool.clone()), Arc::new(RealClock)),
+            pool,
+        }
+    }
+}
+
+#[async_trait]
+impl Backend for PostgresBackend {
+    fn users(&self) -> &(dyn UserRepository + Send + Sync) {
+        &self.users
+    }
+
+    fn profiles(&self) -> &(dyn ProfileRepository + Send + Sync) {
+        &self.profiles
+    }
+
+    fn unit_of_work(&self) -> &(dyn UnitOfWork + Send + Sync) {
+        &self.unit_of_work
+    }
+
+    async fn soft_delete_user(&self, id: i64) {
+        sqlx::query("UPDATE users SET deleted = true WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .unwrap();
+    }
+}
+
+macro_rules! contract {
+    ($($case:ident),* $(,)?) => {
+        mod memory {
+            $(
+                #[tokio::test]
+                async fn $case() {
+                    super::$case(&super::MemoryBackend::new()).await;
+                }
+            )*
+        }
+
+        mod postgres {
+            use real_world_axum_sqlx::config::db::PgPool;
+
+            $(
+                #[sqlx::test]
+                async fn $case(pool: PgPool) {
+                    super::$case(&super::PostgresBackend::new(pool)).await;
+                }
+            )*
+        }
+    };
+}
+
+contract!(
+    registry_assigns_distinct_ids,
+    registry_rejects_taken_email_or_username,
+    finders_return_registered_user,
+    finders_fail_for_unknown_user,
+    soft_deleted_user_is_hidden_but_still_taken,
+    update_overwrites_fields,
+    update_bumps_version,
+    update_rejects_stale_version,
+    update_rejects_taken_email,
+    update_fails_for_deleted_user,
+    versioned_update_fails_for_deleted_user,
+    follow_is_directional,
+    unfollow_clears_follow,
+    follow_again_after_unfollow,
+    transaction_commit_keeps_changes,
+    transaction_dropped_without_commit_rolls_back,
+);
+
+async fn register(backend: &impl Backend, username: &str) -> User {
+    let registry = UserRegistry::new(
+        username.to_owned(),
+        format!("{username}@example.com"),
+        String::from("hashed-password"),
+    );
+    backend.users().registry(registry).await.unwrap()
+}
+
+fn update_of(user: &User, email: &str, bio: Option<&str>) -> UserUpdate {
+    UserUpdate::new(
+        Some(email.to_owned()),
+        Some(user.user_name().to_owned()),
+        Some(user.password().to_owned()),
+        user.image().clone(),
+        bio.map(str::to_owned),
+    )
+}
+
+async fn registry_assigns_distinct_ids(backend: &impl Backend) {
+    let jake = register(backend, "jake").await;
+    let jane = register(backend, "jane").await;
+
+    assert_ne!(jake.id(), jane.id());
+    assert_eq!(jake.user_name(), "jake");
+    assert_eq!(jake.email(), "jake@example.com");
+    assert_eq!(jake.password(), "hashed-password");
+    assert_eq!(jake.bio(), &None);
+    assert_eq!(jake.image(), &None);
+}
+
+async fn registry_rejects_taken_email_or_username(backend: &impl Backend) {
+    register(backend, "jake").await;
+
+    let same_email = UserRegistry::new(
+        String::from("other"),
+        String::from("jake@example.com"),
+        String::from("hashed-password"),
+    );
+    let same_username = UserRegistry::new(
+        String::from("jake"),
+        String::from("other@example.com"),
+        String::from("hashed-password"),
+    );
+
+    assert!(backend.users().registry(same_email).await.is_err());
+    assert!(backend.users().registry(same_username).await.is_err());
+}
+
+async fn finders_return_registered_user(backend: &impl Backend) {
+    let jake = register(backend, "jake").await;
+    let users = backend.users();
+
+    let by_email = users.find_by_email(jake.email().clone()).await.unwrap();
+    let by_id = users.find_by_id(jake.id()).await.unwrap();
+    let by_username = users.find_by_username(String::from("jake")).await.unwrap();
+    let locked = users.lock_by_id(jake.id()).await.unwrap();
+
+    assert_eq!(by_email.id(), jake.id());
+    assert_eq!(by_id.email(), jake.email());
+    assert_eq!(by_username.id(), jake.id());
+    assert_eq!(locked.id(), jake.id());
+    assert!(users.is_exist(jake.email().clone()).await.unwrap());
+}
+
+async fn finders_fail_for_unknown_user(backend: &impl Backend) {
+    let users = backend.users();
+
+    assert!(users
+        .find_by_email(String::from("nobody@example.com"))
+        .await
+        .is_err());
+    assert!(users.find_by_id(404).await.is_err());
+    assert!(users.lock_by_id(404).await.is_err());
+    assert!(users
+        .find_by_username(String::from("nobody"))
+        .await
+        .is_err());
+    assert!(!users
+        .is_exist(String::from("nobody@example.com"))
+        .await
+        .unwrap());
+}
+
+async fn soft_deleted_user_is_hidden_but_still_taken(backend: &impl Backend) {
+    let jake = register(backend, "jake").await;
+    backend.soft_delete_user(jake.id()).await;
+    let users = backend.users();
+
+    assert!(users.find_by_email(jake.email().clone()).await.is_err());
+    assert!(users.find_by_id(jake.id()).await.is_err());
+    assert!(users.find_by_username(String::from("jake")).await.is_err());
+    assert!(users.is_exist(jake.email().clone()).await.unwrap());
+
+    let again = UserRegistry::new(
+        String::from("jake"),
+        jake.email().clone(),
+        String::from("hashed-password"),
+    );
+    assert!(users.registry(again).await.is_err());
+}
+
+async fn update_overwrites_fields(backend: &impl Backend) {
+    let jake = register(backend, "jake").await;
+
+    let updated = backend
+        .users()
+        .update(jake.id(), update_of(&jake, "new@example.com", Some("bio")))
+        .await
+        .unwrap();
+
+    assert_eq!(updated.id(), jake.id());
+    assert_eq!(updated.email(), "new@example.com");
+    assert_eq!(updated.bio(), &Some(String::from("bio")));
+    let reloaded = backend.users().find_by_id(jake.id()).await.unwrap();
+    assert_eq!(reloaded.email(), "new@example.com");
+    assert_eq!(reloaded.bio(), &Some(String::from("bio")));
+}
+
+async fn update_bumps_version(backend: &impl Backend) {
+    let jake = register(backend, "jake").await;
+
+    let updated = backend
+        .users()
+        .update(jake.id(), update_of(&jake, "new@example.com", None))
+        .await
+        .unwrap();
+
+    assert_eq!(jake.version(), 1);
+    assert_eq!(updated.version(), 2);
+    let reloaded = backend.users().find_by_id(jake.id()).await.unwrap();
+    assert_eq!(reloaded.version(), 2);
+}
+
+async fn update_rejects_stale_version(backend: &impl Backend) {
+    let jake = register(backend, "jake").await;
+    let users = backend.users();
+    users
+        .update(
+            jake.id(),
+            update_of(&jake, "first@example.com", None).expect_version(Some(1)),
+        )
+        .await
+        .unwrap();
+
+    let stale = users
+        .update(
+            jake.id(),
+            update_of(&jake, "second@example.com", None).expect_version(Some(1)),
+        )
+        .await;
+
+    assert!(matches!(
+        stale.err().map(|err| err.downcast::<AppError>()),
+        Some(Ok(AppError::PreconditionFailed))
+    ));
+    let reloaded = users.find_by_id(jake.id()).await.unwrap();
+    assert_eq!(reloaded.email(), "first@example.com");
+}
+
+async fn update_rejects_taken_email(backend: &impl Backend) {
+    let jake = register(backend, "jake").await;
+    let jane = register(backend, "jane").await;
+
+    let result = backend
+        .users()
+        .update(jake.id(), update_of(&jake, jane.email(), None))
+        .await;
+
+    assert!(result.is_err());
+    let unchanged = backend.users().find_by_id(jake.id()).await.unwrap();
+    assert_eq!(unchanged.email(), "jake@example.com");
+}
+
+async fn update_fails_for_deleted_user(backend: &impl Backend) {
+    let jake = register(backend, "jake").await;
+    backend.soft_delete_user(jake.id()).await;
+
+    let result = backend
+        .users()
+        .update(jake.id(), update_of(&jake, "new@example.com", None))
+        .await;
+
+    assert!(result.is_err());
+}
+
+async fn versioned_update_fails_for_deleted_user(backend: &impl Backend) {
+    let jake = register(backend, "jake").await;
+    backend.soft_delete_user(jake.id()).await;
+
+    let result = backend
+        .users()
+        .update(
+            jake.id(),
+            update_of(&jake, "new@example.com", None).expect_version(Some(1)),
+        )
+        .await;
+
+    assert!(matches!(
+        result.err().map(|err| err.downcast::<AppError>()),
+        Some(Err(_))
+    ));
+}
+
+async fn follow_is_directional(backend: &impl Backend) {
+    let fan = register(backend, "fan").await;
+    let celeb = register(backend, "celeb").await;
+    let profiles = backend.profiles();
+
+    profiles.follow_user(fan.id(), celeb.id()).await.unwrap();
+
+    assert!(profiles.is_follow(fan.id(), celeb.id()).await.unwrap());
+    assert!(!profiles.is_follow(celeb.id(), fan.id()).await.unwrap());
+}
+
+async fn unfollow_clears_follow(backend: &impl Backend) {
+    let fan = register(backend, "fan").await;
+    let celeb = register(backend, "celeb").await;
+    let profiles = backend.profiles();
+    profiles.follow_user(fan.id(), celeb.id()).await.unwrap();
+
+    profiles.unfollow(fan.id(), celeb.id()).await.unwrap();
+
+    assert!(!profiles.is_follow(fan.id(), celeb.id()).await.unwrap());
+    // Unfollowing someone who is not followed is not an error.
+    profiles.unfollow(fan.id(), celeb.id()).await.unwrap();
+}
+
+async fn follow_again_after_unfollow(backend: &impl Backend) {
+    let fan = register(backend, "fan").await;
+    let celeb = register(backend, "celeb").await;
+    let profiles = backend.profiles();
+
+    profiles.follow_user(fan.id(), celeb.id()).await.unwrap();
+    profiles.unfollow(fan.id(), celeb.id()).await.unwrap();
+    profiles.follow_user(fan.id(), celeb.id()).await.unwrap();
+
+    assert!(profiles.is_follow(fan.id(), celeb.id()).await.unwrap());
+}
+
+async fn transaction_commit_keeps_changes(backend: &impl Backend) {
+    let celeb = register(backend, "celeb").await;
+
+    let transaction = backend.unit_of_work().begin().await.unwrap();
+    let fan = transaction
+        .users()
+        .registry(UserRegistry::new(
+            String::from("fan"),
+            String::from("fan@example.com"),
+            String::from("hashed-password"),
+        ))
+        .await
+        .unwrap();
+    transaction
+        .profiles()
+        .follow_user(fan.id(), celeb.id())
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+
+    let reloaded = backend.users().find_by_id(fan.id()).await.unwrap();
+    assert_eq!(reloaded.user_name(), "fan");
+    assert!(backend
+        .profiles()
+        .is_follow(fan.id(), celeb.id())
+        .await
+        .unwrap());
+}
+
+async fn transaction_dropped_without_commit_rolls_back(backend: &impl Backend) {
+    let jake = register(backend, "jake").await;
+    let celeb = register(backend, "celeb").await;
+
+    let transaction = backend.unit_of_work().begin().await.unwrap();
+    transaction
+        .users()
+        .update(jake.id(), update_of(&jake, "new@example.com", Some("bio")))
+        .await
+        .unwrap();
+    transaction
+        .profiles()
+        .follow_user(jake.id(), celeb.id())
+        .await
+        .unwrap();
+    drop(transaction);
+
+    let reloaded = backend.users().find_by_id(jake.id()).await.unwrap();
+    assert_eq!(reloaded.email(), "jake@example.com");
+    assert_eq!(reloaded.bio(), &None);
+    assert!(!backend
+        .profiles()
+        .is_follow(jake.id(), celeb.id())
+        .await
+        .unwrap());
+}
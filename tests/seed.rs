@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use real_world_axum_sqlx::auth::clock::RealClock;
+use real_world_axum_sqlx::auth::hash_password::ArgonHash;
+use real_world_axum_sqlx::config::db::{DbPool, PgPool};
+use real_world_axum_sqlx::seed::{SeedOptions, Seeder};
+
+async fn row_counts(pool: &PgPool) -> Vec<i64> {
+    let mut counts = Vec::new();
+    for table in ["users", "user_follow", "article", "article_comment"] {
+        let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        counts.push(count);
+    }
+    counts
+}
+
+#[sqlx::test]
+async fn failed_seed_leaves_nothing_behind(pool: PgPool) {
+    let seeder = Seeder::new(
+        Arc::new(ArgonHash),
+        Arc::new(RealClock),
+        DbPool::new(pool.clone()),
+    );
+    seeder.run(&SeedOptions::default()).await.unwrap();
+    let seeded = row_counts(&pool).await;
+
+    // This seed starts with a new user but runs into one of the first run later on.
+    let options = SeedOptions {
+        seed: 3,
+        ..SeedOptions::default()
+    };
+    let result = seeder.run(&options).await;
+
+    let err = result.unwrap_err();
+    assert!(format!("{err:#}").contains("User Insert Failed"), "{err:#}");
+    assert_eq!(row_counts(&pool).await, seeded);
+}
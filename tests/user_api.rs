@@ -0,0 +1,374 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use chrono::{Duration, NaiveDateTime, TimeZone, Utc};
+use common::{email_of, TestApp, PASSWORD, TEST_SETTINGS};
+use real_world_axum_sqlx::auth::clock::MockClock;
+use real_world_axum_sqlx::config::db::PgPool;
+use real_world_axum_sqlx::profile::repository::memory::InMemoryProfileRepository;
+use real_world_axum_sqlx::unit_of_work::memory::InMemoryUnitOfWork;
+use real_world_axum_sqlx::user::repository::memory::InMemoryUserRepository;
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+
+#[sqlx::test]
+async fn register_returns_user_with_token(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let response = app
+        .post(
+            "/api/users",
+            None,
+            json!({ "user": { "username": "jake", "email": "jake@jake.jake", "password": PASSWORD } }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    let user = &response.body["user"];
+    assert_eq!(user["username"], "jake");
+    assert_eq!(user["email"], "jake@jake.jake");
+    assert!(user["token"]
+        .as_str()
+        .is_some_and(|token| !token.is_empty()));
+    assert!(user["bio"].is_null());
+    assert!(user["image"].is_null());
+}
+
+#[sqlx::test]
+async fn register_rejects_taken_email(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("jake").await;
+
+    let response = app
+        .post(
+            "/api/users",
+            None,
+            json!({ "user": { "username": "other", "email": email_of("jake"), "password": PASSWORD } }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn register_validates_request(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let short_password = app
+        .post(
+            "/api/users",
+            None,
+            json!({ "user": { "username": "jake", "email": email_of("jake"), "password": "short" } }),
+        )
+        .await;
+    let missing_email = app
+        .post(
+            "/api/users",
+            None,
+            json!({ "user": { "username": "jake", "password": PASSWORD } }),
+        )
+        .await;
+
+    assert_eq!(short_password.status, StatusCode::BAD_REQUEST);
+    assert_eq!(missing_email.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[sqlx::test]
+async fn login_returns_token(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("jake").await;
+
+    let response = app
+        .post(
+            "/api/users/login",
+            None,
+            json!({ "user": { "email": email_of("jake"), "password": PASSWORD } }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["user"]["username"], "jake");
+    assert!(response.body["user"]["token"].is_string());
+}
+
+#[sqlx::test]
+async fn login_rejects_wrong_password_and_unknown_email(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    app.register("jake").await;
+
+    let wrong_password = app
+        .post(
+            "/api/users/login",
+            None,
+            json!({ "user": { "email": email_of("jake"), "password": "wrong-password" } }),
+        )
+        .await;
+    let unknown_email = app
+        .post(
+            "/api/users/login",
+            None,
+            json!({ "user": { "email": email_of("nobody"), "password": PASSWORD } }),
+        )
+        .await;
+
+    assert_eq!(wrong_password.status, StatusCode::BAD_REQUEST);
+    assert_eq!(unknown_email.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn current_user_requires_token(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let anonymous = app.get("/api/user", None).await;
+    let garbage = app.get("/api/user", Some("not-a-jwt")).await;
+
+    assert_eq!(anonymous.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(garbage.status, StatusCode::BAD_REQUEST);
+}
+
+#[sqlx::test]
+async fn current_user_returns_token_owner(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let token = app.register("jake").await;
+
+    let response = app.get("/api/user", Some(&token)).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["user"]["username"], "jake");
+    assert_eq!(response.body["user"]["email"], email_of("jake"));
+}
+
+#[sqlx::test]
+async fn token_expires_when_clock_passes_ttl(pool: PgPool) {
+    let clock = Arc::new(MockClock::new(Utc::now()));
+    let app = TestApp::with_clock(pool, clock.clone()).await;
+    let token = app.register("jake").await;
+
+    clock.advance(Duration::hours(3) - Duration::minutes(1));
+    let before_expiry = app.get("/api/user", Some(&token)).await;
+    clock.advance(Duration::minutes(2));
+    let after_expiry = app.get("/api/user", Some(&token)).await;
+
+    assert_eq!(before_expiry.status, StatusCode::OK);
+    assert_ne!(after_expiry.status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn timestamps_come_from_clock(pool: PgPool) {
+    let registered_at = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+    let clock = Arc::new(MockClock::new(registered_at));
+    let app = TestApp::with_clock(pool, clock.clone()).await;
+    let token = app.register("jake").await;
+
+    clock.advance(Duration::hours(1));
+    let update = app
+        .put(
+            "/api/user",
+            Some(&token),
+            json!({ "user": { "bio": "I work at statefarm" } }),
+        )
+        .await;
+    assert_eq!(update.status, StatusCode::OK);
+
+    let (registration_date, modified_date): (NaiveDateTime, NaiveDateTime) = sqlx::query_as(
+        "SELECT registration_date, modified_date FROM users WHERE username = 'jake'",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(registration_date, registered_at.naive_utc());
+    assert_eq!(
+        modified_date,
+        (registered_at + Duration::hours(1)).naive_utc()
+    );
+}
+
+#[sqlx::test]
+async fn container_accepts_in_memory_repository(pool: PgPool) {
+    let app = TestApp::with_container(pool, TEST_SETTINGS, |builder| {
+        let users = Arc::new(InMemoryUserRepository::new());
+        let profiles = Arc::new(InMemoryProfileRepository::new());
+        builder
+            .user_repository(users.clone())
+            .profile_repository(profiles.clone())
+            .unit_of_work(Arc::new(InMemoryUnitOfWork::new(users, profiles)))
+            .build()
+    })
+    .await;
+    let token = app.register("jake").await;
+
+    let response = app.get("/api/user", Some(&token)).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["user"]["username"], "jake");
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, 0);
+}
+
+#[sqlx::test]
+async fn update_user_changes_given_fields(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let token = app.register("jake").await;
+
+    let response = app
+        .put(
+            "/api/user",
+            Some(&token),
+            json!({ "user": { "bio": "I work at statefarm", "image": "https://i.imgur.com/jake.png" } }),
+        )
+        .await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    let user = &response.body["user"];
+    assert_eq!(user["username"], "jake");
+    assert_eq!(user["email"], email_of("jake"));
+    assert_eq!(user["bio"], "I work at statefarm");
+    assert_eq!(user["image"], "https://i.imgur.com/jake.png");
+
+    let current = app.get("/api/user", Some(&token)).await;
+    assert_eq!(current.body["user"]["bio"], "I work at statefarm");
+}
+
+#[sqlx::test]
+async fn update_user_password_is_used_for_login(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let token = app.register("jake").await;
+
+    let response = app
+        .put(
+            "/api/user",
+            Some(&token),
+            json!({ "user": { "password": "new-password" } }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let old_password = app
+        .post(
+            "/api/users/login",
+            None,
+            json!({ "user": { "email": email_of("jake"), "password": PASSWORD } }),
+        )
+        .await;
+    let new_password = app
+        .post(
+            "/api/users/login",
+            None,
+            json!({ "user": { "email": email_of("jake"), "password": "new-password" } }),
+        )
+        .await;
+
+    assert_eq!(old_password.status, StatusCode::BAD_REQUEST);
+    assert_eq!(new_password.status, StatusCode::OK);
+}
+
+#[sqlx::test]
+async fn update_user_with_stale_if_match_fails(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let token = app.register("jake").await;
+    let update = |etag: &str, bio: &str| {
+        Request::put("/api/user")
+            .header(header::AUTHORIZATION, format!("Token {token}"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::IF_MATCH, etag)
+            .body(Body::from(json!({ "user": { "bio": bio } }).to_string()))
+            .unwrap()
+    };
+
+    let read = app.get("/api/user", Some(&token)).await;
+    let etag = read.headers[header::ETAG].to_str().unwrap().to_owned();
+    let phone = app.send(update(&etag, "from phone")).await;
+    let laptop = app.send(update(&etag, "from laptop")).await;
+    let current = app.get("/api/user", Some(&token)).await;
+
+    assert_eq!(etag, "\"1\"");
+    assert_eq!(phone.status, StatusCode::OK);
+    assert_eq!(phone.headers[header::ETAG], "\"2\"");
+    assert_eq!(laptop.status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(laptop.body["code"], 41200);
+    assert_eq!(current.body["user"]["bio"], "from phone");
+    assert_eq!(current.headers[header::ETAG], "\"2\"");
+}
+
+#[sqlx::test]
+async fn concurrent_updates_keep_each_others_fields(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+    let token = app.register("jake").await;
+
+    let (bio, image) = tokio::join!(
+        app.put(
+            "/api/user",
+            Some(&token),
+            json!({ "user": { "bio": "I work at statefarm" } })
+        ),
+        app.put(
+            "/api/user",
+            Some(&token),
+            json!({ "user": { "image": "https://i.imgur.com/jake.png" } })
+        ),
+    );
+    let current = app.get("/api/user", Some(&token)).await;
+
+    assert_eq!(bio.status, StatusCode::OK);
+    assert_eq!(image.status, StatusCode::OK);
+    assert_eq!(current.body["user"]["bio"], "I work at statefarm");
+    assert_eq!(
+        current.body["user"]["image"],
+        "https://i.imgur.com/jake.png"
+    );
+    assert_eq!(current.headers[header::ETAG], "\"3\"");
+}
+
+#[sqlx::test]
+async fn update_user_requires_token(pool: PgPool) {
+    let app = TestApp::new(pool).await;
+
+    let response = app
+        .put("/api/user", None, json!({ "user": { "bio": "bio" } }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[sqlx::test]
+async fn reads_after_write_stay_on_primary_across_requests(pool: PgPool) {
+    // Nothing listens on port 1, so every read that reaches the replica fails.
+    let replica = PgPoolOptions::new()
+        .acquire_timeout(std::time::Duration::from_secs(1))
+        .connect_lazy("postgres://postgres@127.0.0.1:1/realworld")
+        .unwrap();
+    let app = TestApp::with_replica(pool, replica).await;
+    let token = app.register("jake").await;
+    let get_user = |cookie: Option<&str>| {
+        let mut request =
+            Request::get("/api/user").header(header::AUTHORIZATION, format!("Bearer {token}"));
+        if let Some(cookie) = cookie {
+            request = request.header(header::COOKIE, cookie);
+        }
+        request.body(Body::empty()).unwrap()
+    };
+
+    let updated = app
+        .put(
+            "/api/user",
+            Some(&token),
+            json!({ "user": { "bio": "I work at statefarm" } }),
+        )
+        .await;
+    let set_cookie = updated.headers[header::SET_COOKIE].to_str().unwrap();
+    let cookie = set_cookie.split(';').next().unwrap();
+    let unpinned = app.send(get_user(None)).await;
+    let pinned = app.send(get_user(Some(cookie))).await;
+
+    assert_eq!(updated.status, StatusCode::OK);
+    assert!(set_cookie.starts_with("read_primary_until="));
+    assert_eq!(pinned.status, StatusCode::OK);
+    assert_eq!(pinned.body["user"]["bio"], "I work at statefarm");
+    assert_ne!(unpinned.status, StatusCode::OK);
+}